@@ -0,0 +1,261 @@
+use crate::render::*;
+use crate::resource::*;
+use crate::texture_id::*;
+use crate::utility::*;
+
+use serde::Deserialize;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+
+// Names of the clips in the `animations` section of `info.json`.
+#[derive(Deserialize)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Clip {
+    Idle,
+    Walk,
+    Run,
+    Skid,
+    Jump,
+    Climb,
+    Swim,
+    Death,
+    ShellSpin,
+}
+
+// Everything that is animated through clips instead of a single texture.
+#[derive(Deserialize)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug)]
+pub enum Sprite {
+    Player,
+    BigPlayer,
+    Goomba,
+    Koopa,
+    Blooper,
+    CheepCheep,
+    FlyingKoopa,
+    PiranhaPlant,
+    Shell,
+    BuzzyBeetle,
+    Spiny,
+    HammerBro,
+    Hammer,
+}
+
+#[derive(Deserialize)]
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+pub struct AnimationClip {
+    pub texture: TextureId,
+    // Index of the first frame of the clip in the texture.
+    #[serde(default)]
+    pub first:   u32,
+    #[serde(default = "default_frames")]
+    pub frames:  u32,
+    // Frames shown per second.
+    #[serde(default = "default_speed")]
+    pub speed:   u32,
+    // Clips that don't loop stop at their last frame, or switch to the next
+    // clip if there is one.
+    #[serde(default = "default_looping")]
+    pub looping: bool,
+    #[serde(default)]
+    pub next:    Option<Clip>,
+}
+
+#[derive(Copy, Clone)]
+#[derive(PartialEq, Eq)]
+#[derive(Debug)]
+pub enum AnimationEvent {
+    Transition { from: Clip, to: Clip },
+    Finished(Clip),
+}
+
+// Animation state of a single entity. It has its own timer, so clips always
+// start from their first frame.
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+pub struct Animation {
+    sprite:      Sprite,
+    clip:        Clip,
+    timer:       u32,
+    tint:        Option<Color>,
+    upside_down: bool,
+}
+
+impl Sprite {
+    // Texture used for clips missing in `info.json`.
+    pub fn default_texture(self) -> TextureId {
+        match self {
+            Sprite::Player => TextureId::PlayerStanding,
+            Sprite::BigPlayer => TextureId::BigPlayerStanding,
+            Sprite::Goomba => TextureId::EnemyGoomba,
+            Sprite::Koopa => TextureId::EnemyKoopa,
+            Sprite::Blooper => TextureId::EnemyBlooper,
+            Sprite::CheepCheep => TextureId::EnemyCheepCheep,
+            Sprite::FlyingKoopa => TextureId::EnemyFlyingKoopa,
+            Sprite::PiranhaPlant => TextureId::EnemyPiranhaPlant,
+            Sprite::Shell => TextureId::EnemyShell,
+            Sprite::BuzzyBeetle => TextureId::EnemyBuzzyBeetle,
+            Sprite::Spiny => TextureId::EnemySpiny,
+            Sprite::HammerBro => TextureId::EnemyHammerBro,
+            Sprite::Hammer => TextureId::Hammer,
+        }
+    }
+}
+
+impl AnimationClip {
+    pub fn looping(texture: TextureId, frames: u32, speed: u32) -> Self {
+        AnimationClip {
+            texture,
+            first: 0,
+            frames,
+            speed,
+            looping: true,
+            next: None,
+        }
+    }
+
+    // Number of game frames it takes to show every frame of the clip once.
+    pub fn duration(&self) -> u32 {
+        (self.frames * FPS).div_ceil(self.speed.max(1))
+    }
+
+    pub fn frame_index(&self, timer: u32) -> u32 {
+        let frames = self.frames.max(1);
+        let frame = (u64::from(timer) * u64::from(self.speed) / u64::from(FPS))
+            as u32;
+        if self.looping {
+            self.first + frame % frames
+        } else {
+            self.first + frame.min(frames - 1)
+        }
+    }
+}
+
+impl Animation {
+    pub fn new(sprite: Sprite, clip: Clip) -> Animation {
+        Animation {
+            sprite,
+            clip,
+            timer: 0,
+            tint: None,
+            upside_down: false,
+        }
+    }
+
+    pub fn clip(&self) -> Clip {
+        self.clip
+    }
+
+    // Changing the sprite keeps the clip playing, e.g. when the player
+    // grows in the middle of a jump.
+    pub fn set_sprite(&mut self, sprite: Sprite) {
+        self.sprite = sprite;
+    }
+
+    pub fn set_tint(&mut self, tint: Option<Color>) {
+        self.tint = tint;
+    }
+
+    pub fn turn_upside_down(&mut self) {
+        self.upside_down = true;
+    }
+
+    pub fn play(&mut self, clip: Clip) -> Option<AnimationEvent> {
+        if clip == self.clip {
+            return None;
+        }
+
+        let from = self.clip;
+        self.clip = clip;
+        self.timer = 0;
+        Some(AnimationEvent::Transition { from, to: clip })
+    }
+
+    // A clip that got shorter while playing, e.g. after a reload, finishes
+    // right away. The timer of a finished clip is parked at its maximum, so
+    // it finishes only once and keeps showing its last frame.
+    pub fn update(&mut self, res: &ResourceManager) -> Option<AnimationEvent> {
+        let clip = res.animation_clip(self.sprite, self.clip);
+        if clip.looping || self.timer == u32::MAX {
+            self.timer = self.timer.saturating_add(1);
+            return None;
+        }
+
+        self.timer += 1;
+        if self.timer < clip.duration() {
+            return None;
+        }
+        self.timer = u32::MAX;
+
+        match clip.next {
+            Some(next) => self.play(next),
+            None => Some(AnimationEvent::Finished(self.clip)),
+        }
+    }
+}
+
+// Sprites face left, entities moving to the right are flipped.
+impl Drawable for Animation {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        let animation = data.object;
+        let clip = res.animation_clip(animation.sprite, animation.clip);
+        let info = res.entity_texture_info(clip.texture);
+
+        let (x, y) = data.position;
+        let (off_x, off_y) = info.hitbox_offset();
+        let (x, y) = (x + off_x, y + off_y);
+
+        let width = (info.width as f64 * data.scale) as u32;
+        let height = (info.height as f64 * data.scale) as u32;
+        if !data.camera.in_view(rect!(x, y, width, height)) {
+            return;
+        }
+
+        let sprite_x = clip.frame_index(animation.timer) * info.width;
+        let src_region = rect!(sprite_x, 0, info.width, info.height);
+
+        let (cam_x, cam_y) = data.camera.translate_coords((x, y));
+        let dest = rect!(cam_x, cam_y, width, height);
+
+        let flip = data.mode == DrawMode::EntityDirection(XDirection::Right);
+        let path = info.path.clone();
+
+        let texture = res.texture(&path);
+        let tinted = match animation.tint {
+            Some(tint) => res.tinted_texture(&path, tint),
+            None => None,
+        };
+        data.renderer
+            .canvas
+            .copy_ex(
+                tinted.unwrap_or(&texture),
+                src_region,
+                dest,
+                0.0,
+                Point::new(0, 0),
+                flip,
+                animation.upside_down,
+            )
+            .expect("Failed to draw an animation!");
+    }
+}
+
+// for serde_json default values purposes
+fn default_frames() -> u32 {
+    1
+}
+
+fn default_speed() -> u32 {
+    1
+}
+
+fn default_looping() -> bool {
+    true
+}
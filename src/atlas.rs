@@ -0,0 +1,107 @@
+use crate::render::*;
+use crate::utility::*;
+
+use sdl2::image::LoadSurface;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture};
+use sdl2::surface::Surface;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+const ATLAS_WIDTH: u32 = 2048;
+const PADDING: u32 = 1;
+
+// Image drawn in place of textures that are missing or failed to load.
+pub const PLACEHOLDER: &str = "placeholder";
+pub const PLACEHOLDER_SIZE: u32 = 64;
+
+pub struct TextureAtlas<'a> {
+    texture: Texture<'a>,
+    regions: HashMap<String, Rect>,
+}
+
+impl<'a> TextureAtlas<'a> {
+    // Packs every image into a single texture using rows ("shelves") of
+    // images sorted by height.
+    pub fn new(
+        creator: &'a TextureFactory,
+        textures_path: &Path,
+        paths: &[&str],
+    ) -> Result<TextureAtlas<'a>> {
+        let mut images = vec![];
+        for &path in paths {
+            let file = textures_path.join(format!("{}.png", path));
+            let mut surface = match Surface::from_file(&file) {
+                Ok(surface) => surface,
+                Err(_) if path == PLACEHOLDER => placeholder_surface()?,
+                Err(err) => {
+                    eprintln!("Failed to load texture {} ({})", path, err);
+                    placeholder_surface()?
+                },
+            };
+            surface.set_blend_mode(BlendMode::None)?;
+            images.push((path, surface));
+        }
+
+        images.sort_by_key(|(_, surface)| std::cmp::Reverse(surface.height()));
+
+        let mut regions = HashMap::new();
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for (path, surface) in images.iter() {
+            let (width, height) = surface.size();
+            if width > ATLAS_WIDTH {
+                return Err(format!("Texture {} is too wide!", path).into());
+            }
+
+            if x + width > ATLAS_WIDTH {
+                x = 0;
+                y += shelf_height + PADDING;
+                shelf_height = 0;
+            }
+
+            regions.insert(path.to_string(), rect!(x, y, width, height));
+            x += width + PADDING;
+            shelf_height = shelf_height.max(height);
+        }
+
+        let atlas_height = (y + shelf_height).max(1);
+        let mut atlas =
+            Surface::new(ATLAS_WIDTH, atlas_height, PixelFormatEnum::RGBA8888)?;
+
+        for (path, surface) in images.iter() {
+            surface.blit(None, &mut atlas, regions[*path])?;
+        }
+
+        let mut texture = creator.create_texture_from_surface(&atlas)?;
+        texture.set_blend_mode(BlendMode::Blend);
+
+        Ok(TextureAtlas { texture, regions })
+    }
+
+    pub fn texture(&self) -> &Texture<'a> {
+        &self.texture
+    }
+
+    pub fn origin(&self, path: &str) -> Option<(i32, i32)> {
+        self.regions.get(path).map(|region| (region.x(), region.y()))
+    }
+}
+
+// Magenta and black checkerboard, which stands out in every level theme.
+pub fn placeholder_surface() -> Result<Surface<'static>> {
+    const SQUARE: u32 = PLACEHOLDER_SIZE / 4;
+    let size = PLACEHOLDER_SIZE;
+    let mut surface = Surface::new(size, size, PixelFormatEnum::RGBA8888)?;
+    surface.fill_rect(None, Color::RGB(0, 0, 0))?;
+
+    for y in 0..size / SQUARE {
+        for x in (y % 2..size / SQUARE).step_by(2) {
+            let square = rect!(x * SQUARE, y * SQUARE, SQUARE, SQUARE);
+            surface.fill_rect(square, Color::RGB(255, 0, 255))?;
+        }
+    }
+    Ok(surface)
+}
@@ -0,0 +1,96 @@
+use sdl2::mixer::{self, Music, AUDIO_S16LSB, DEFAULT_CHANNELS};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const FREQUENCY: i32 = 44_100;
+const CHUNK_SIZE: i32 = 1024;
+
+#[derive(Copy, Clone)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug)]
+pub enum Track {
+    StarPower,
+}
+
+// Plays one music track at a time. Without an audio device or with missing
+// music files the game just stays silent.
+pub struct Jukebox {
+    music_path: PathBuf,
+    enabled:    bool,
+    // Tracks that failed to load are kept as `None`, so they are reported
+    // only once.
+    tracks:     HashMap<Track, Option<Music<'static>>>,
+    playing:    Option<Track>,
+}
+
+impl Track {
+    fn file_name(self) -> &'static str {
+        match self {
+            Track::StarPower => "star.wav",
+        }
+    }
+}
+
+impl Jukebox {
+    pub fn new(music_path: &Path) -> Jukebox {
+        let opened = mixer::open_audio(
+            FREQUENCY,
+            AUDIO_S16LSB,
+            DEFAULT_CHANNELS,
+            CHUNK_SIZE,
+        );
+        if let Err(err) = &opened {
+            eprintln!("Failed to open the audio device, music is off: {}", err);
+        }
+
+        Jukebox {
+            music_path: music_path.to_path_buf(),
+            enabled:    opened.is_ok(),
+            tracks:     HashMap::new(),
+            playing:    None,
+        }
+    }
+
+    // Loops the track until it's stopped, a track that is already playing
+    // goes on without restarting.
+    pub fn play(&mut self, track: Track) {
+        if !self.enabled || self.playing == Some(track) {
+            return;
+        }
+
+        let path = self.music_path.join(track.file_name());
+        let music = self.tracks.entry(track).or_insert_with(|| {
+            Music::from_file(&path)
+                .map_err(|err| {
+                    eprintln!("Failed to load {}: {}", path.display(), err);
+                })
+                .ok()
+        });
+
+        if let Some(music) = music {
+            match music.play(-1) {
+                Ok(()) => self.playing = Some(track),
+                Err(err) => eprintln!("Failed to play music: {}", err),
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if self.playing.take().is_some() {
+            Music::halt();
+        }
+    }
+
+    pub fn pause(&self) {
+        if self.playing.is_some() {
+            Music::pause();
+        }
+    }
+
+    pub fn resume(&self) {
+        if self.playing.is_some() {
+            Music::resume();
+        }
+    }
+}
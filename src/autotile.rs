@@ -0,0 +1,191 @@
+use crate::block::*;
+use crate::level::*;
+
+// Kinds of terrain painted as a whole, the editor picks the edge and corner
+// pieces based on the neighbouring blocks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Terrain {
+    Ground,
+    Rock,
+    TreeTop,
+    Pipe,
+}
+
+// Which of the neighbouring blocks belong to the same terrain.
+#[derive(Copy, Clone)]
+struct Neighbours {
+    left:  bool,
+    right: bool,
+    above: bool,
+}
+
+impl Terrain {
+    pub fn next(self) -> Terrain {
+        match self {
+            Terrain::Ground => Terrain::Rock,
+            Terrain::Rock => Terrain::TreeTop,
+            Terrain::TreeTop => Terrain::Pipe,
+            Terrain::Pipe => Terrain::Ground,
+        }
+    }
+
+    pub fn prev(self) -> Terrain {
+        match self {
+            Terrain::Ground => Terrain::Pipe,
+            Terrain::Rock => Terrain::Ground,
+            Terrain::TreeTop => Terrain::Rock,
+            Terrain::Pipe => Terrain::TreeTop,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Terrain::Ground => "GROUND",
+            Terrain::Rock => "ROCK",
+            Terrain::TreeTop => "TREETOP",
+            Terrain::Pipe => "PIPE",
+        }
+    }
+
+    // Block shown for the terrain in the editor.
+    pub fn icon(self) -> BlockType {
+        match self {
+            Terrain::Ground => BlockType::GroundMiddle,
+            Terrain::Rock => BlockType::Rock,
+            Terrain::TreeTop => BlockType::TreeLeafsMiddle,
+            Terrain::Pipe => BlockType::PipeUpperLeft,
+        }
+    }
+
+    // Only the vertical pipe parts are auto-tiled, the sideways pipes and
+    // junctions are still placed by hand.
+    pub fn of(kind: BlockType) -> Option<Terrain> {
+        match kind {
+            BlockType::Ground
+            | BlockType::GroundLeft
+            | BlockType::GroundMiddle
+            | BlockType::GroundRight
+            | BlockType::GroundBottom
+            | BlockType::GroundBottomLeft
+            | BlockType::GroundBottomMiddle
+            | BlockType::GroundBottomRight => Some(Terrain::Ground),
+            BlockType::Rock
+            | BlockType::RockLeft
+            | BlockType::RockMiddle
+            | BlockType::RockRight => Some(Terrain::Rock),
+            BlockType::TreeLeafsLeft
+            | BlockType::TreeLeafsMiddle
+            | BlockType::TreeLeafsRight => Some(Terrain::TreeTop),
+            BlockType::PipeUpperLeft
+            | BlockType::PipeUpperRight
+            | BlockType::PipeLowerLeft
+            | BlockType::PipeLowerRight => Some(Terrain::Pipe),
+            _ => None,
+        }
+    }
+
+    fn piece(self, near: Neighbours, left_half: bool) -> BlockType {
+        let Neighbours { left, right, above } = near;
+        match self {
+            Terrain::Ground if above => match (left, right) {
+                (false, false) => BlockType::GroundBottom,
+                (false, true) => BlockType::GroundBottomLeft,
+                (true, false) => BlockType::GroundBottomRight,
+                (true, true) => BlockType::GroundBottomMiddle,
+            },
+            Terrain::Ground => match (left, right) {
+                (false, false) => BlockType::Ground,
+                (false, true) => BlockType::GroundLeft,
+                (true, false) => BlockType::GroundRight,
+                (true, true) => BlockType::GroundMiddle,
+            },
+            Terrain::Rock => match (left, right) {
+                (false, false) => BlockType::Rock,
+                (false, true) => BlockType::RockLeft,
+                (true, false) => BlockType::RockRight,
+                (true, true) => BlockType::RockMiddle,
+            },
+            Terrain::TreeTop => match (left, right) {
+                (false, true) => BlockType::TreeLeafsLeft,
+                (true, false) => BlockType::TreeLeafsRight,
+                _ => BlockType::TreeLeafsMiddle,
+            },
+            Terrain::Pipe => match (above, left_half) {
+                (false, true) => BlockType::PipeUpperLeft,
+                (false, false) => BlockType::PipeUpperRight,
+                (true, true) => BlockType::PipeLowerLeft,
+                (true, false) => BlockType::PipeLowerRight,
+            },
+        }
+    }
+}
+
+impl Level {
+    pub fn paint_terrain(&mut self, pos: (usize, usize), terrain: Terrain) {
+        self.set_block(pos, Block::from(terrain.icon()));
+        self.retile_around(pos);
+    }
+
+    pub fn erase_terrain(&mut self, pos: (usize, usize)) {
+        self.set_block(pos, Block::default());
+        self.retile_around(pos);
+    }
+
+    // Updates the pieces next to a changed block. Pipes are two blocks wide,
+    // so a change can flip the halves of the whole pipe row to its right.
+    pub fn retile_around(&mut self, (x, y): (usize, usize)) {
+        let rows = y.saturating_sub(1)..=(y + 1).min(LEVEL_HEIGHT - 1);
+        let cols = x.saturating_sub(1)..=(x + 1).min(LEVEL_WIDTH - 1);
+        for row in rows {
+            for col in cols.clone() {
+                self.retile((col, row));
+            }
+        }
+
+        let mut col = x + 2;
+        while col < LEVEL_WIDTH && self.is_pipe((col, y)) {
+            self.retile((col, y));
+            col += 1;
+        }
+    }
+
+    fn retile(&mut self, pos: (usize, usize)) {
+        let mut block = self.get_block(pos);
+        if let Some(terrain) = Terrain::of(block.kind()) {
+            let near = self.neighbours(pos, terrain);
+            let left_half = self.pipe_run_length(pos) % 2 == 1;
+            block.set_kind(terrain.piece(near, left_half));
+            self.set_block(pos, block);
+        }
+    }
+
+    fn terrain_at(&self, pos: (usize, usize)) -> Option<Terrain> {
+        Terrain::of(self.get_block(pos).kind())
+    }
+
+    fn is_pipe(&self, pos: (usize, usize)) -> bool {
+        self.terrain_at(pos) == Some(Terrain::Pipe)
+    }
+
+    // Terrain continues past the left and right edges of the level.
+    fn neighbours(
+        &self,
+        (x, y): (usize, usize),
+        terrain: Terrain,
+    ) -> Neighbours {
+        let same = |pos| self.terrain_at(pos) == Some(terrain);
+        Neighbours {
+            left:  x == 0 || same((x - 1, y)),
+            right: x + 1 == LEVEL_WIDTH || same((x + 1, y)),
+            above: y > 0 && same((x, y - 1)),
+        }
+    }
+
+    // Number of pipe blocks in the row ending with the given one.
+    fn pipe_run_length(&self, (x, y): (usize, usize)) -> usize {
+        (0..=x)
+            .rev()
+            .take_while(|&col| self.is_pipe((col, y)))
+            .count()
+    }
+}
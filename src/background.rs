@@ -52,6 +52,14 @@ impl BackgroundElement {
         self != BackgroundElement::Air
     }
 
+    pub fn mirrored(self) -> Self {
+        match self {
+            BackgroundElement::GrassLeft => BackgroundElement::GrassRight,
+            BackgroundElement::GrassRight => BackgroundElement::GrassLeft,
+            element => element,
+        }
+    }
+
     pub fn next(self) -> Self {
         FromPrimitive::from_u8((self as u8 + 1) % MAX_BG).unwrap()
     }
@@ -70,40 +78,37 @@ impl Drawable for ThemedBackgroundElement {
             return;
         }
 
-        let (src_region, dest, path) = {
-            let info = res.bg_texture_info(bg);
-            let (x, y) = data.position;
-            let width = (info.width as f64 * data.scale) as u32;
-            let height = (info.height as f64 * data.scale) as u32;
-
-            if !data.camera.in_view(rect!(x, y, width, height)) {
-                return;
-            }
+        let info = res.bg_texture_info(bg);
+        let (x, y) = data.position;
+        let width = (info.width as f64 * data.scale) as u32;
+        let height = (info.height as f64 * data.scale) as u32;
 
-            let theme = data.object.theme;
+        if !data.camera.in_view(rect!(x, y, width, height)) {
+            return;
+        }
 
-            let sprite_x = (info.frame_index(data.tick) * info.width) as i32;
-            let sprite_y = (info.variant_index(theme) * info.height) as i32;
+        let theme = data.object.theme;
 
-            let src_region = rect!(sprite_x, sprite_y, info.width, info.height);
-            let (cam_x, cam_y) = data.camera.translate_coords((x, y));
-            let dest = rect!(cam_x, cam_y, width, height);
+        let sprite_x = (info.frame_index(data.tick) * info.width) as i32;
+        let sprite_y = (info.variant_index(theme) * info.height) as i32;
 
-            if data.mode == DrawMode::EditorSelection {
-                let rect = rect!(x, y, info.width, info.height);
-                data.renderer.canvas.set_draw_color(Color::RGB(255, 0, 0));
-                data.renderer.canvas.draw_rect(rect).expect(
-                    "Failed to draw selection rectangle in the editor!",
-                );
-            }
+        let src_region = info
+            .atlas_region(rect!(sprite_x, sprite_y, info.width, info.height));
+        let (cam_x, cam_y) = data.camera.translate_coords((x, y));
+        let dest = rect!(cam_x, cam_y, width, height);
 
-            (src_region, dest, info.path.clone())
-        };
+        if data.mode == DrawMode::EditorSelection {
+            let rect = rect!(x, y, info.width, info.height);
+            data.renderer.canvas.set_draw_color(Color::RGB(255, 0, 0));
+            data.renderer
+                .canvas
+                .draw_rect(rect)
+                .expect("Failed to draw selection rectangle in the editor!");
+        }
 
-        let texture = res.texture(&path);
         data.renderer
             .canvas
-            .copy(&texture, src_region, dest)
+            .copy(res.atlas(), src_region, dest)
             .unwrap();
     }
 }
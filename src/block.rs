@@ -1,5 +1,4 @@
 use crate::entity::*;
-use crate::entity::*;
 use crate::hitbox::*;
 use crate::level::*;
 use crate::render::*;
@@ -16,17 +15,26 @@ use num_traits::FromPrimitive;
 use sdl2::pixels::Color;
 
 pub const BLOCK_SIZE: u32 = 64;
+// Frames a multi-coin block keeps paying out coins after its first bump.
+pub const MULTI_COIN_TIME: u16 = FPS as u16 * 4;
 
 #[derive(Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Debug)]
 pub struct Block {
     kind:     BlockType,
     contents: Option<Collectible>,
+    // Hidden blocks aren't drawn and don't collide until the player bumps
+    // them from below.
+    #[serde(default, skip_serializing_if = "is_false")]
+    hidden:   bool,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct RealBlock {
-    pub block: Block,
-    pub state: BlockState,
+    pub block:      Block,
+    pub state:      BlockState,
+    // Frames left until a multi-coin block stops paying out, `None` until
+    // it's bumped for the first time.
+    pub coin_timer: Option<u16>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -84,9 +92,13 @@ pub struct ThemedBlock {
 #[derive(PartialEq, Eq, Hash)]
 pub enum Collectible {
     Coins(u8),
+    // Pays out a coin for every bump until `MULTI_COIN_TIME` runs out.
+    MultiCoin,
     Mushroom,
+    OneUp,
     Star,
     Flower,
+    Vine,
 }
 
 const MAX_BLOCK: u8 = BlockType::Air as u8;
@@ -96,16 +108,14 @@ impl From<Block> for RealBlock {
         RealBlock {
             block,
             state: BlockState::Static,
+            coin_timer: None,
         }
     }
 }
 
 impl Default for RealBlock {
     fn default() -> Self {
-        RealBlock {
-            block: Block::default(),
-            state: BlockState::Static,
-        }
+        RealBlock::from(Block::default())
     }
 }
 
@@ -114,6 +124,7 @@ impl From<BlockType> for Block {
         Block {
             kind:     block_type,
             contents: None,
+            hidden:   false,
         }
     }
 }
@@ -136,11 +147,32 @@ impl Block {
     }
 
     pub fn new(kind: BlockType, contents: Option<Collectible>) -> Self {
-        Block { kind, contents }
+        Block {
+            kind,
+            contents,
+            hidden: false,
+        }
     }
 
     pub fn is_collidable(self) -> bool {
-        self.kind.is_collidable()
+        !self.hidden && self.kind.is_collidable()
+    }
+
+    pub fn is_hidden(self) -> bool {
+        self.hidden
+    }
+
+    // Only bumpable blocks can be hidden, others could never be revealed.
+    pub fn toggle_hidden(&mut self) {
+        self.hidden = !self.hidden && self.is_bumpable();
+    }
+
+    // An empty hidden block turns into a used block instead of breaking.
+    pub fn reveal(&mut self) {
+        if self.hidden && self.is_empty() {
+            self.kind = BlockType::QuestionMarkEmpty;
+        }
+        self.hidden = false;
     }
 
     pub fn is_empty(self) -> bool {
@@ -205,31 +237,6 @@ impl Default for BlockType {
     }
 }
 
-impl RealBlock {
-    pub fn spawn_particles(
-        self,
-        theme: LevelTheme,
-        (x, y): (usize, usize),
-        entities: &mut Vec<Entity>,
-    ) {
-        const NUM_PARTICLES: usize = 4;
-        let particle = EntityType::Particle(Particle::new_fragment(
-            self.block.kind,
-            theme,
-        ));
-
-        let mut rng = rand::thread_rng();
-
-        entities.extend((0..NUM_PARTICLES).map(|_| {
-            let mut entity = Entity::spawn(particle, (x, y));
-            let x = rng.gen_range(-3.0, 3.0);
-            let y = rng.gen_range(-10.0, -5.0);
-            entity.body.accelerate(vec2d!(x, y));
-            entity
-        }));
-    }
-}
-
 impl BlockType {
     fn next(self) -> BlockType {
         let next_id = (self as u8 + 1) % MAX_BLOCK;
@@ -246,6 +253,37 @@ impl BlockType {
         self != BlockType::Air
     }
 
+    // Counterpart of the block in a horizontally flipped level.
+    pub fn mirrored(self) -> BlockType {
+        match self {
+            BlockType::RockLeft => BlockType::RockRight,
+            BlockType::RockRight => BlockType::RockLeft,
+            BlockType::GroundLeft => BlockType::GroundRight,
+            BlockType::GroundRight => BlockType::GroundLeft,
+            BlockType::GroundBottomLeft => BlockType::GroundBottomRight,
+            BlockType::GroundBottomRight => BlockType::GroundBottomLeft,
+            BlockType::PipeUpperLeft => BlockType::PipeUpperRight,
+            BlockType::PipeUpperRight => BlockType::PipeUpperLeft,
+            BlockType::PipeLowerLeft => BlockType::PipeLowerRight,
+            BlockType::PipeLowerRight => BlockType::PipeLowerLeft,
+            BlockType::PipeSidewaysLeftBottom => {
+                BlockType::PipeSidewaysRightBottom
+            },
+            BlockType::PipeSidewaysRightBottom => {
+                BlockType::PipeSidewaysLeftBottom
+            },
+            BlockType::PipeSidewaysLeftUpper => {
+                BlockType::PipeSidewaysRightUpper
+            },
+            BlockType::PipeSidewaysRightUpper => {
+                BlockType::PipeSidewaysLeftUpper
+            },
+            BlockType::TreeLeafsLeft => BlockType::TreeLeafsRight,
+            BlockType::TreeLeafsRight => BlockType::TreeLeafsLeft,
+            block => block,
+        }
+    }
+
     fn is_collidable(self) -> bool {
         self != BlockType::Air
     }
@@ -261,42 +299,61 @@ impl BlockType {
 impl Drawable for ThemedBlock {
     fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
         let block = data.object.block;
+        let editor = matches!(
+            data.mode,
+            DrawMode::Editor | DrawMode::EditorSelection
+        );
 
-        if !block.is_visible() {
+        if !block.is_visible() || (block.hidden && !editor) {
             return;
         }
 
-        let (src_region, dest, path) = {
-            let info = res.block_texture_info(block.kind);
+        let info = res.block_texture_info(block.kind);
 
-            let (x, y) = data.position;
-            let width = (info.width as f64 * data.scale) as u32;
-            let height = (info.height as f64 * data.scale) as u32;
-            if !data.camera.in_view(rect!(x, y, width, height)) {
-                return;
-            }
-            let theme = data.object.theme;
-            let sprite_x = (info.frame_index(data.tick) * info.width) as i32;
-            let sprite_y = (info.variant_index(theme) * info.height) as i32;
-            let src_region = rect!(sprite_x, sprite_y, info.width, info.height);
-            let (cam_x, cam_y) = data.camera.translate_coords((x, y));
-            let dest = rect!(cam_x, cam_y, width, height);
-
-            if data.mode == DrawMode::EditorSelection {
-                let rect = rect!(x, y, info.width, info.height);
-                data.renderer.canvas.set_draw_color(Color::RGB(255, 0, 0));
-                data.renderer.canvas.draw_rect(rect).expect(
-                    "Failed to draw selection rectangle in the editor!",
-                );
-            }
-            (src_region, dest, info.path.clone())
-        };
+        let (x, y) = data.position;
+        let width = (info.width as f64 * data.scale) as u32;
+        let height = (info.height as f64 * data.scale) as u32;
+        if !data.camera.in_view(rect!(x, y, width, height)) {
+            return;
+        }
+        let theme = data.object.theme;
+        let sprite_x = (info.frame_index(data.tick) * info.width) as i32;
+        let sprite_y = (info.variant_index(theme) * info.height) as i32;
+        let src_region = info
+            .atlas_region(rect!(sprite_x, sprite_y, info.width, info.height));
+        let (cam_x, cam_y) = data.camera.translate_coords((x, y));
+        let dest = rect!(cam_x, cam_y, width, height);
+
+        if data.mode == DrawMode::EditorSelection {
+            let rect = rect!(x, y, info.width, info.height);
+            data.renderer.canvas.set_draw_color(Color::RGB(255, 0, 0));
+            data.renderer
+                .canvas
+                .draw_rect(rect)
+                .expect("Failed to draw selection rectangle in the editor!");
+        }
 
         data.renderer
             .canvas
-            .copy(&res.texture(&path), src_region, dest)
+            .copy(res.atlas(), src_region, dest)
             .expect("Failed to draw a block!");
 
+        // Hidden blocks are washed out and framed in the editor.
+        if block.hidden {
+            data.renderer
+                .canvas
+                .set_draw_color(Color::RGBA(255, 255, 255, 150));
+            data.renderer
+                .canvas
+                .fill_rect(dest)
+                .expect("Failed to draw a hidden block!");
+            data.renderer.canvas.set_draw_color(Color::RGB(0, 160, 255));
+            data.renderer
+                .canvas
+                .draw_rect(dest)
+                .expect("Failed to draw a hidden block!");
+        }
+
         if data.mode == DrawMode::Editor && !block.is_empty() {
             pass_draw!(data, &block.contents.unwrap()).show(res);
         }
@@ -306,28 +363,38 @@ impl Drawable for ThemedBlock {
 impl Collectible {
     pub fn next(self) -> Self {
         match self {
-            Collectible::Coins(_) => Collectible::Mushroom,
-            Collectible::Mushroom => Collectible::Star,
-            Collectible::Star => Collectible::Coins(1),
+            Collectible::Coins(_) => Collectible::MultiCoin,
+            Collectible::MultiCoin => Collectible::Mushroom,
+            Collectible::Mushroom => Collectible::OneUp,
+            Collectible::OneUp => Collectible::Star,
+            Collectible::Star => Collectible::Vine,
+            Collectible::Vine => Collectible::Coins(1),
             Collectible::Flower => Collectible::Coins(1),
         }
     }
 
     pub fn prev(self) -> Self {
         match self {
-            Collectible::Coins(_) => Collectible::Star,
-            Collectible::Star => Collectible::Mushroom,
-            Collectible::Mushroom => Collectible::Coins(1),
+            Collectible::Coins(_) => Collectible::Vine,
+            Collectible::Vine => Collectible::Star,
+            Collectible::Star => Collectible::OneUp,
+            Collectible::OneUp => Collectible::Mushroom,
+            Collectible::Mushroom => Collectible::MultiCoin,
+            Collectible::MultiCoin => Collectible::Coins(1),
             Collectible::Flower => Collectible::Coins(1),
         }
     }
 
     pub fn texture_id(self) -> TextureId {
         match self {
-            Collectible::Coins(..) => TextureId::CollectibleCoin,
+            Collectible::Coins(..) | Collectible::MultiCoin => {
+                TextureId::CollectibleCoin
+            },
             Collectible::Star => TextureId::CollectibleStar,
             Collectible::Mushroom => TextureId::CollectibleMushroom,
+            Collectible::OneUp => TextureId::CollectibleOneUp,
             Collectible::Flower => TextureId::CollectibleFlower,
+            Collectible::Vine => TextureId::CollectibleVine,
         }
     }
 }
@@ -359,8 +426,15 @@ impl Drawable for Collectible {
             .expect("Failed to draw a collectible entity!");
 
         if data.mode == DrawMode::Editor {
-            if let Collectible::Coins(amount) = data.object {
-                let amount_str = format!("{}", amount);
+            // Multi-coin blocks show for how many seconds they pay out.
+            let label = match data.object {
+                Collectible::Coins(amount) => Some(format!("{}", amount)),
+                Collectible::MultiCoin => {
+                    Some(format!("{}s", u32::from(MULTI_COIN_TIME) / FPS))
+                },
+                _ => None,
+            };
+            if let Some(amount_str) = label {
                 let text = TextBuilder::new(&amount_str)
                     .color(Color::RGB(0, 0, 200))
                     .alignment(TextAlignment::TotalCenter)
@@ -375,3 +449,8 @@ impl Drawable for Collectible {
         }
     }
 }
+
+// for serde_json skipping default values purposes
+fn is_false(value: &bool) -> bool {
+    !value
+}
@@ -0,0 +1,489 @@
+use crate::controller::*;
+use crate::interface::*;
+use crate::level::*;
+use crate::render::*;
+use crate::resource::*;
+use crate::snapshot::*;
+use crate::state::*;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+use std::time::SystemTime;
+
+const THUMBNAIL_SCALE: f64 = 0.04;
+const LIST_X: i32 = 20;
+const LIST_Y: i32 = 80;
+const LIST_WIDTH: u32 = 940;
+const ROW_HEIGHT: u32 = 64;
+const ROW_GAP: u32 = 8;
+const VISIBLE_ROWS: usize = 8;
+const MESSAGE_TIME: u32 = 3 * FPS;
+
+// Lists the level files with their thumbnails and manages them before
+// opening one of them in the editor.
+pub struct LevelBrowser {
+    levels:   Vec<LevelFile>,
+    pack:     Vec<String>,
+    selected: usize,
+    scroll:   usize,
+    buttons:  ButtonColumn<BrowserAction>,
+    prompt:   Option<Prompt>,
+    message:  String,
+    msg_time: u32,
+    // levels whose thumbnails haven't been rendered yet, one is rendered
+    // each frame so that opening the browser doesn't freeze the game
+    missing:  Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BrowserAction {
+    Open,
+    New,
+    Duplicate,
+    Rename,
+    Delete,
+    TogglePack,
+    Back,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    Name(BrowserAction),
+    ConfirmDelete,
+}
+
+impl LevelBrowser {
+    pub fn new(res: &ResourceManager) -> LevelBrowser {
+        let mut browser = LevelBrowser {
+            levels:   vec![],
+            pack:     vec![],
+            selected: 0,
+            scroll:   0,
+            buttons:  build_buttons(false),
+            prompt:   None,
+            message:  String::new(),
+            msg_time: 0,
+            missing:  vec![],
+        };
+        browser.refresh(res, None);
+        browser
+    }
+
+    pub fn reload(&mut self, res: &ResourceManager) {
+        let name = self.selected_level().map(|level| level.name.clone());
+        self.refresh(res, name.as_deref());
+    }
+
+    // Reads the levels directory again, keeping the given level selected.
+    fn refresh(&mut self, res: &ResourceManager, select: Option<&str>) {
+        self.levels = res.list_levels();
+        self.pack = res.load_level_list().unwrap_or_else(|err| {
+            self.show_message(&err.to_string());
+            vec![]
+        });
+        self.missing = self
+            .levels
+            .iter()
+            .filter(|level| res.thumbnail(level).is_none())
+            .map(|level| level.name.clone())
+            .collect();
+
+        if let Some(name) = select {
+            if let Some(i) = self.levels.iter().position(|l| l.name == name) {
+                self.selected = i;
+            }
+        }
+        self.select(self.selected);
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.levels.len().saturating_sub(1));
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.selected + 1 - VISIBLE_ROWS;
+        }
+        self.buttons = build_buttons(self.in_pack());
+    }
+
+    fn selected_level(&self) -> Option<&LevelFile> {
+        self.levels.get(self.selected)
+    }
+
+    fn in_pack(&self) -> bool {
+        self.selected_level()
+            .is_some_and(|level| self.pack.contains(&level.name))
+    }
+
+    fn show_message(&mut self, message: &str) {
+        self.message = String::from(message);
+        self.msg_time = MESSAGE_TIME;
+    }
+
+    fn rows(&self) -> impl Iterator<Item = (usize, Rect)> {
+        let visible = self.scroll..self.levels.len();
+        visible.take(VISIBLE_ROWS).enumerate().map(|(row, i)| {
+            let y = LIST_Y + (row as u32 * (ROW_HEIGHT + ROW_GAP)) as i32;
+            (i, rect!(LIST_X, y, LIST_WIDTH, ROW_HEIGHT))
+        })
+    }
+
+    pub fn update_and_get_activity(
+        &mut self,
+        state: &mut SharedState,
+    ) -> Option<Activity> {
+        self.msg_time = self.msg_time.saturating_sub(1);
+
+        if let Some(prompt) = self.prompt {
+            return self.update_prompt(prompt, state);
+        }
+
+        let controller = &state.controller;
+        if controller.was_key_pressed(Key::Escape) {
+            return Some(Activity::new_main_menu(&state.resources));
+        }
+        if controller.was_key_pressed(Key::Enter) {
+            return self.open(state);
+        }
+
+        if controller.was_key_pressed(Key::Up) {
+            self.select(self.selected.saturating_sub(1));
+        } else if controller.was_key_pressed(Key::Down) {
+            self.select(self.selected + 1);
+        }
+
+        let scroll = controller.mouse().scroll();
+        let max_scroll = self.levels.len().saturating_sub(VISIBLE_ROWS);
+        if scroll > 0 {
+            self.scroll = self.scroll.saturating_sub(1);
+        } else if scroll < 0 {
+            self.scroll = (self.scroll + 1).min(max_scroll);
+        }
+
+        if controller.was_button_pressed(MButton::Left) {
+            let pos = controller.mouse().pos();
+            let row = self.rows().find(|(_, rect)| rect.contains_point(pos));
+            if let Some((i, _)) = row {
+                self.select(i);
+            }
+        }
+
+        let action = self.buttons.effect_if_clicked(&state.controller).copied();
+        match action {
+            Some(BrowserAction::Open) => return self.open(state),
+            Some(BrowserAction::Back) => {
+                return Some(Activity::new_main_menu(&state.resources));
+            },
+            Some(BrowserAction::TogglePack) => {
+                self.toggle_pack(&mut state.resources)
+            },
+            Some(BrowserAction::Delete) if self.selected_level().is_some() => {
+                self.prompt = Some(Prompt::ConfirmDelete);
+            },
+            Some(BrowserAction::New) => {
+                self.prompt = Some(Prompt::Name(BrowserAction::New));
+                state.text_input.start();
+            },
+            Some(action) if self.selected_level().is_some() => {
+                self.prompt = Some(Prompt::Name(action));
+                state.text_input.start();
+            },
+            _ => (),
+        }
+
+        None
+    }
+
+    fn open(&self, state: &SharedState) -> Option<Activity> {
+        self.selected_level()
+            .map(|level| Activity::new_editor(&state.resources, &level.name))
+    }
+
+    fn update_prompt(
+        &mut self,
+        prompt: Prompt,
+        state: &mut SharedState,
+    ) -> Option<Activity> {
+        let controller = &state.controller;
+        if controller.was_key_pressed(Key::Escape) {
+            if let Prompt::Name(..) = prompt {
+                state.text_input.end();
+            }
+            self.prompt = None;
+            return None;
+        }
+        if !controller.was_key_pressed(Key::Enter) {
+            return None;
+        }
+
+        self.prompt = None;
+        let action = match prompt {
+            Prompt::ConfirmDelete => {
+                self.delete(&mut state.resources);
+                return None;
+            },
+            Prompt::Name(action) => action,
+        };
+
+        let name = state.text_input.end();
+        let name = name.trim();
+        if let Err(problem) = validate_name(name) {
+            self.show_message(problem);
+            return None;
+        }
+        if state.resources.level_exists(name) {
+            self.show_message("A LEVEL WITH THIS NAME ALREADY EXISTS");
+            return None;
+        }
+
+        let res = &mut state.resources;
+        let result = match (action, self.selected_level()) {
+            (BrowserAction::New, _) => {
+                match res.save_level(name, &Level::new()) {
+                    Ok(()) => return Some(Activity::new_editor(res, name)),
+                    Err(err) => Err(err.into()),
+                }
+            },
+            (BrowserAction::Duplicate, Some(level)) => {
+                res.duplicate_level(&level.name, name)
+            },
+            (BrowserAction::Rename, Some(level)) => {
+                let old_name = level.name.clone();
+                res.rename_level(&old_name, name)
+            },
+            _ => Ok(()),
+        };
+
+        match result {
+            Ok(()) => self.refresh(res, Some(name)),
+            Err(err) => self.show_message(&err.to_string()),
+        }
+        None
+    }
+
+    fn delete(&mut self, res: &mut ResourceManager) {
+        let name = match self.selected_level() {
+            Some(level) => level.name.clone(),
+            None => return,
+        };
+
+        match res.delete_level(&name) {
+            Ok(()) => self.refresh(res, None),
+            Err(err) => self.show_message(&err.to_string()),
+        }
+    }
+
+    // Levels are added at the end of the level pack.
+    fn toggle_pack(&mut self, res: &mut ResourceManager) {
+        let name = match self.selected_level() {
+            Some(level) => level.name.clone(),
+            None => return,
+        };
+
+        // the list is read again, so that a broken file isn't overwritten
+        let mut pack = match res.load_level_list() {
+            Ok(pack) => pack,
+            Err(err) => {
+                self.show_message(&err.to_string());
+                return;
+            },
+        };
+        if pack.contains(&name) {
+            pack.retain(|level| *level != name);
+        } else {
+            pack.push(name.clone());
+        }
+
+        match res.save_level_list(&pack) {
+            Ok(()) => self.refresh(res, Some(&name)),
+            Err(err) => self.show_message(&err.to_string()),
+        }
+    }
+
+    // Levels that fail to load are left without a thumbnail.
+    fn render_thumbnail(
+        &mut self,
+        renderer: &mut Renderer,
+        res: &mut ResourceManager,
+    ) {
+        let name = match self.missing.pop() {
+            Some(name) => name,
+            None => return,
+        };
+        let level = match self.levels.iter().find(|l| l.name == name) {
+            Some(level) => level,
+            None => return,
+        };
+
+        if let Ok(prototype) = res.load_level(&name) {
+            prototype.cache_thumbnail(level, THUMBNAIL_SCALE, renderer, res);
+        }
+    }
+
+    pub fn draw(&mut self, renderer: &mut Renderer, state: &mut SharedState) {
+        let res = &mut state.resources;
+        self.render_thumbnail(renderer, res);
+        renderer.clear(Color::RGB(88, 100, 255));
+
+        renderer
+            .draw(&text!("LEVELS"))
+            .position((LIST_X, 20))
+            .scale(0.3)
+            .show(res);
+
+        if self.levels.is_empty() {
+            renderer
+                .draw(&text!("NO LEVELS YET, CREATE ONE WITH NEW"))
+                .position((LIST_X, LIST_Y))
+                .scale(0.2)
+                .show(res);
+        }
+
+        let now = SystemTime::now();
+        for (i, rect) in self.rows() {
+            let level = &self.levels[i];
+            self.draw_row(renderer, res, level, rect, now);
+
+            if i == self.selected {
+                renderer.canvas.set_draw_color(Color::RGB(255, 255, 0));
+                renderer.canvas.draw_rect(rect).unwrap();
+            }
+        }
+
+        renderer.draw(&self.buttons).show(res);
+
+        if self.msg_time > 0 {
+            renderer
+                .draw(&text!(&self.message))
+                .position((LIST_X, LOGICAL_HEIGHT as i32 - 40))
+                .scale(0.2)
+                .show(res);
+        }
+
+        match self.prompt {
+            Some(Prompt::Name(action)) => {
+                let title = match action {
+                    BrowserAction::New => "New level name: ",
+                    BrowserAction::Duplicate => "Name of the copy: ",
+                    _ => "New name: ",
+                };
+                let input = state.text_input.text();
+                draw_prompt(renderer, &mut state.resources, title, input);
+            },
+            Some(Prompt::ConfirmDelete) => {
+                let name = self.selected_level().map_or("", |l| &l.name);
+                let title = format!("Delete level '{}'?", name);
+                let hint = "ENTER to confirm, ESC to cancel";
+                draw_prompt(renderer, &mut state.resources, &title, hint);
+            },
+            None => (),
+        }
+    }
+
+    fn draw_row(
+        &self,
+        renderer: &mut Renderer,
+        res: &mut ResourceManager,
+        level: &LevelFile,
+        rect: Rect,
+        now: SystemTime,
+    ) {
+        const PADDING: i32 = 6;
+
+        renderer.canvas.set_draw_color(Color::RGBA(0, 0, 0, 128));
+        renderer.canvas.fill_rect(rect).unwrap();
+
+        let thumb_width =
+            (f64::from(LEVEL_PIXEL_WIDTH) * THUMBNAIL_SCALE) as u32;
+        let thumb_height =
+            (f64::from(LEVEL_PIXEL_HEIGHT) * THUMBNAIL_SCALE) as u32;
+        let thumb_rect = rect!(
+            rect.x() + PADDING,
+            rect.y() + (ROW_HEIGHT - thumb_height) as i32 / 2,
+            thumb_width,
+            thumb_height
+        );
+        match res.thumbnail(level) {
+            Some(texture) => {
+                renderer.canvas.copy(texture, None, thumb_rect).unwrap();
+            },
+            None => {
+                renderer.canvas.set_draw_color(Color::RGB(40, 40, 40));
+                renderer.canvas.fill_rect(thumb_rect).unwrap();
+            },
+        }
+
+        let text_x = thumb_rect.right() + 2 * PADDING;
+        renderer
+            .draw(&text!(&level.name))
+            .position((text_x, rect.y() + PADDING))
+            .scale(0.2)
+            .show(res);
+
+        let mut details = format_age(now, level.modified);
+        if let Some(i) = self.pack.iter().position(|name| *name == level.name) {
+            details = format!("#{} - {}", i + 1, details);
+        }
+        renderer
+            .draw(&text!(&details))
+            .position((text_x, rect.y() + ROW_HEIGHT as i32 / 2 + PADDING))
+            .scale(0.15)
+            .show(res);
+    }
+}
+
+fn build_buttons(in_pack: bool) -> ButtonColumn<BrowserAction> {
+    const WIDTH: u32 = 280;
+    const MARGIN: u32 = 20;
+    let pack = if in_pack { "DROP FROM PACK" } else { "ADD TO PACK" };
+
+    ButtonColumnBuilder::new()
+        .width(WIDTH)
+        .height(60)
+        .separation(12)
+        .shift_x(((LOGICAL_WIDTH - WIDTH) / 2 - MARGIN) as i32)
+        .add(("OPEN", BrowserAction::Open))
+        .add(("NEW", BrowserAction::New))
+        .add(("DUPLICATE", BrowserAction::Duplicate))
+        .add(("RENAME", BrowserAction::Rename))
+        .add(("DELETE", BrowserAction::Delete))
+        .add((pack, BrowserAction::TogglePack))
+        .add(("BACK", BrowserAction::Back))
+        .build()
+}
+
+fn format_age(now: SystemTime, modified: SystemTime) -> String {
+    let seconds = now
+        .duration_since(modified)
+        .map_or(0, |age| age.as_secs());
+    match seconds {
+        0..=59 => String::from("EDITED JUST NOW"),
+        60..=3599 => format!("EDITED {} MIN AGO", seconds / 60),
+        3600..=86399 => format!("EDITED {} H AGO", seconds / 3600),
+        _ => format!("EDITED {} DAYS AGO", seconds / 86400),
+    }
+}
+
+fn draw_prompt(
+    renderer: &mut Renderer,
+    res: &mut ResourceManager,
+    title: &str,
+    input: &str,
+) {
+    renderer.fill(Color::RGBA(0, 0, 0, 192));
+    let center = renderer.center();
+
+    renderer
+        .draw(&centered_text!(title))
+        .position(center)
+        .shift((0, -100))
+        .scale(0.2)
+        .show(res);
+
+    renderer
+        .draw(&centered_text!(input))
+        .position(center)
+        .scale(0.25)
+        .show(res);
+}
@@ -0,0 +1,205 @@
+use crate::level::*;
+use crate::paths::*;
+use crate::resource::*;
+use crate::snapshot::*;
+use crate::utility::*;
+use crate::validation::*;
+
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "\
+usage: mario-clone [OPTIONS] [COMMAND]
+
+commands:
+    menu                  open the main menu (default)
+    play [LEVEL]          start the game, from the given level if present
+    edit LEVEL            open the given level in the editor
+    validate [FILE...]    check level files, the whole level pack if none
+                          are given
+    convert INPUT OUTPUT  convert a level file, .json output files are
+                          pretty printed and .lvl files are compact
+    render INPUT OUTPUT   render a whole level file into a PNG image
+    help                  print this message
+
+options:
+    --pack NAME           use the level pack resources/levels/NAME.json
+    --resources DIR       read the game resources from DIR
+    --level LEVEL         same as `play LEVEL`
+    --scale FACTOR        scale of the images made by `render` (1.0)
+    --game                make `render` draw the level as it's played,
+                          without the markers of the editor
+    -h, --help            print this message
+";
+
+#[derive(Clone)]
+pub enum Launch {
+    MainMenu,
+    Game(Option<String>),
+    Editor(String),
+}
+
+pub enum Command {
+    Launch(Launch),
+    Tool(Tool),
+}
+
+// Commands that run without a window.
+pub enum Tool {
+    Validate(Vec<String>),
+    Convert(PathBuf, PathBuf),
+    Render(PathBuf, PathBuf),
+    Help,
+}
+
+pub struct Options {
+    pub command:   Command,
+    pub pack:      String,
+    pub resources: Option<PathBuf>,
+    pub scale:     f64,
+    pub game:      bool,
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(args: I) -> Result<Options> {
+        let mut pack = String::from(DEFAULT_LEVEL_PACK);
+        let mut level = None;
+        let mut resources = None;
+        let mut scale = 1.0;
+        let mut game = false;
+        let mut positional = vec![];
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--pack" => pack = expect_value(&mut args, &arg)?,
+                "--level" => level = Some(expect_value(&mut args, &arg)?),
+                "--resources" => {
+                    resources = Some(expect_value(&mut args, &arg)?.into());
+                },
+                "--scale" => {
+                    scale = expect_value(&mut args, &arg)?
+                        .parse()
+                        .ok()
+                        .filter(|&scale: &f64| scale > 0.0)
+                        .ok_or("scale has to be a positive number")?;
+                },
+                "--game" => game = true,
+                "-h" | "--help" => positional = vec![String::from("help")],
+                flag if flag.starts_with('-') && flag.len() > 1 => {
+                    return Err(format!("unknown option '{}'", flag).into());
+                },
+                _ => positional.push(arg),
+            }
+        }
+
+        let command = match positional.split_first() {
+            None if level.is_some() => Command::Launch(Launch::Game(level)),
+            None => Command::Launch(Launch::MainMenu),
+            Some((name, args)) => Command::new(name, args, level)?,
+        };
+
+        Ok(Options {
+            command,
+            pack,
+            resources,
+            scale,
+            game,
+        })
+    }
+}
+
+impl Command {
+    fn new(
+        name: &str,
+        args: &[String],
+        level: Option<String>,
+    ) -> Result<Command> {
+        let command = match (name, args) {
+            ("help", _) => Command::Tool(Tool::Help),
+            ("menu", []) => Command::Launch(Launch::MainMenu),
+            ("play", []) => Command::Launch(Launch::Game(level)),
+            ("play", [level]) => {
+                Command::Launch(Launch::Game(Some(level.clone())))
+            },
+            ("edit", [level]) => Command::Launch(Launch::Editor(level.clone())),
+            ("validate", files) => {
+                Command::Tool(Tool::Validate(files.to_vec()))
+            },
+            ("convert", [input, output]) => Command::Tool(Tool::Convert(
+                PathBuf::from(input),
+                PathBuf::from(output),
+            )),
+            ("render", [input, output]) => Command::Tool(Tool::Render(
+                PathBuf::from(input),
+                PathBuf::from(output),
+            )),
+            ("menu", _)
+            | ("play", _)
+            | ("edit", _)
+            | ("convert", _)
+            | ("render", _) => {
+                return Err(format!("wrong arguments for '{}'", name).into());
+            },
+            _ => return Err(format!("unknown command '{}'", name).into()),
+        };
+
+        Ok(command)
+    }
+}
+
+fn expect_value<I: Iterator<Item = String>>(
+    args: &mut I,
+    flag: &str,
+) -> Result<String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for '{}'", flag).into())
+}
+
+// Runs a command that doesn't need a window and returns the exit code. The
+// resources are only looked for by the commands that use them.
+pub fn run_tool(tool: &Tool, options: &Options) -> i32 {
+    let find_paths = || GamePaths::find(options.resources.as_deref());
+    let result = match tool {
+        Tool::Help => {
+            print!("{}", USAGE);
+            Ok(true)
+        },
+        Tool::Validate(files) => find_paths()
+            .and_then(|paths| validate_levels(&paths, &options.pack, files)),
+        Tool::Convert(input, output) => {
+            read_level(input)
+                .and_then(|level| write_level(output, &level))
+                .map(|_| true)
+                .map_err(|err| err.into())
+        },
+        Tool::Render(input, output) => find_paths()
+            .and_then(|paths| render_level(input, output, &paths, options))
+            .map(|_| true),
+    };
+
+    match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            2
+        },
+    }
+}
+
+fn render_level(
+    input: &Path,
+    output: &Path,
+    paths: &GamePaths,
+    options: &Options,
+) -> Result<()> {
+    let level = read_level(input)?;
+    let ttf = sdl2::ttf::init()?;
+    let image = if options.game {
+        let level = PlayableLevel::from(level);
+        snapshot_playable_level(&level, &ttf, paths, options.scale)?
+    } else {
+        snapshot_level(&level, &ttf, paths, options.scale)?
+    };
+    save_png(&image, output)
+}
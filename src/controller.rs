@@ -48,6 +48,25 @@ pub enum Key {
     Enter,
     Tab,
     Ctrl,
+    Fullscreen,
+    Preview,
+    Alt,
+    Copy,
+    Cut,
+    Paste,
+    Flip,
+    Move,
+    Delete,
+    Stamp,
+    Tool,
+    ShrinkBrush,
+    GrowBrush,
+    Palette,
+    Snap,
+    Reload,
+    Hide,
+    RaiseWater,
+    LowerWater,
 
     Invalid,
 }
@@ -72,6 +91,25 @@ impl From<Keycode> for Key {
             Keycode::D => Key::Right,
             Keycode::W => Key::Up,
             Keycode::S => Key::Down,
+            Keycode::F11 => Key::Fullscreen,
+            Keycode::P => Key::Preview,
+            Keycode::LAlt => Key::Alt,
+            Keycode::C => Key::Copy,
+            Keycode::X => Key::Cut,
+            Keycode::V => Key::Paste,
+            Keycode::H => Key::Flip,
+            Keycode::M => Key::Move,
+            Keycode::Delete => Key::Delete,
+            Keycode::B => Key::Stamp,
+            Keycode::T => Key::Tool,
+            Keycode::LeftBracket => Key::ShrinkBrush,
+            Keycode::RightBracket => Key::GrowBrush,
+            Keycode::Q => Key::Palette,
+            Keycode::G => Key::Snap,
+            Keycode::F5 => Key::Reload,
+            Keycode::I => Key::Hide,
+            Keycode::PageUp => Key::RaiseWater,
+            Keycode::PageDown => Key::LowerWater,
             _ => Key::Invalid,
         }
     }
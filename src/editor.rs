@@ -1,33 +1,77 @@
+use crate::autotile::*;
 use crate::background::*;
 use crate::block::*;
 use crate::controller::*;
 use crate::enemy::*;
 use crate::entity::*;
+use crate::error::*;
 use crate::interface::*;
 use crate::level::*;
+use crate::palette::*;
+use crate::parallax::*;
+use crate::properties::*;
 use crate::render::*;
 use crate::resource::*;
+use crate::stamp::*;
 use crate::state::*;
+use crate::tool::*;
+use crate::utility::*;
+use crate::validation::*;
 
 use sdl2::pixels::Color;
 
 use std::mem::replace;
 
+// How long the warnings found while saving stay on the screen.
+const WARNING_TIME: u32 = FPS * 6;
+const AUTOSAVE_TIME: u32 = FPS * 20;
+
 pub struct Editor {
-    camera:     Camera,
-    level:      Level,
-    level_name: String,
-    paused:     bool,
-    menu:       ButtonColumn<ButtonEffect>,
-    selected:   Selection,
+    camera:       Camera,
+    level:        Level,
+    level_name:   String,
+    paused:       bool,
+    menu:         ButtonColumn<ButtonEffect>,
+    selected:     Selection,
+    parallax:     bool,
+    warnings:     Vec<String>,
+    warn_timer:   u32,
+    region:       Option<Region>,
+    drag_start:   Option<(usize, usize)>,
+    clipboard:    Option<Stamp>,
+    floating:     Option<Stamp>,
+    stamps:       Vec<(String, Stamp)>,
+    naming_stamp: bool,
+    tool:         Tool,
+    brush_size:   usize,
+    shape:        Option<ShapeDrag>,
+    palette:      Palette,
+    snap:         bool,
+    picked:       Option<usize>,
+    drag_offset:  Option<(i32, i32)>,
+    autosave:     u32,
+    // serialized level as it was last saved and as it was last written to
+    // the recovery file, to detect unsaved changes
+    saved:        String,
+    autosaved:    String,
 }
 
 #[derive(Clone, Copy)]
-enum Selection {
+struct ShapeDrag {
+    start:  (usize, usize),
+    end:    (usize, usize),
+    button: MButton,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Selection {
     Block(Block),
+    Terrain(Terrain),
     Background(BackgroundElement),
     Collectible(Collectible),
     Enemy(EnemyType),
+    Warp,
+    Stamp(usize),
 }
 
 enum ButtonEffect {
@@ -37,23 +81,72 @@ enum ButtonEffect {
 }
 
 impl Editor {
-    pub fn new(resources: &ResourceManager, name: &str) -> Editor {
-        let level = resources.load_level(name).unwrap_or_default();
+    // Levels that don't exist yet are created when they are saved.
+    pub fn new(
+        resources: &ResourceManager,
+        name: &str,
+    ) -> ResourceResult<Editor> {
+        let level = match resources.load_level(name) {
+            Err(err) if err.is_missing_file() => Level::default(),
+            result => result?,
+        };
+        Ok(Editor::with_level(resources, name, level))
+    }
+
+    // Restored changes stay in the recovery file until they are saved.
+    pub fn recover(
+        resources: &ResourceManager,
+        recovery: Recovery,
+    ) -> ResourceResult<Editor> {
+        let name = recovery.level_name.clone();
+        let level = recovery.into_level()?;
+        let mut editor = Editor::with_level(resources, &name, level);
+        editor.saved = resources
+            .load_level(&name)
+            .map(|saved| level_state(&saved))
+            .unwrap_or_default();
+        Ok(editor)
+    }
+
+    fn with_level(
+        resources: &ResourceManager,
+        name: &str,
+        level: Level,
+    ) -> Editor {
         let buttons = ButtonColumnBuilder::new()
             .add(("RESUME", ButtonEffect::Resume))
             .add(("SAVE", ButtonEffect::Save))
             .add(("MENU", ButtonEffect::Menu))
             .build();
 
-        let cam_y = LEVEL_HEIGHT as u32 * BLOCK_SIZE - SCREEN_HEIGHT;
+        let cam_y = LEVEL_HEIGHT as u32 * BLOCK_SIZE - LOGICAL_HEIGHT;
 
         Editor {
             camera: Camera::new(0, cam_y as i32),
             paused: false,
-            level,
             level_name: String::from(name),
             menu: buttons,
             selected: Selection::Block(Block::default_visible()),
+            parallax: false,
+            warnings: vec![],
+            warn_timer: 0,
+            region: None,
+            drag_start: None,
+            clipboard: None,
+            floating: None,
+            stamps: resources.load_stamps(),
+            naming_stamp: false,
+            tool: Tool::Pencil,
+            brush_size: 1,
+            shape: None,
+            palette: Palette::new(),
+            snap: true,
+            picked: None,
+            drag_offset: None,
+            autosave: 0,
+            saved: level_state(&level),
+            autosaved: level_state(&level),
+            level,
         }
     }
 
@@ -66,7 +159,7 @@ impl Editor {
 
         let x_movement = if x < MOVEMENT_MARGIN {
             -MOVEMENT_SPEED
-        } else if x > SCREEN_WIDTH as i32 - MOVEMENT_MARGIN {
+        } else if x > LOGICAL_WIDTH as i32 - MOVEMENT_MARGIN {
             MOVEMENT_SPEED
         } else {
             0
@@ -74,7 +167,7 @@ impl Editor {
 
         let y_movement = if y < MOVEMENT_MARGIN {
             -MOVEMENT_SPEED
-        } else if y > SCREEN_HEIGHT as i32 - MOVEMENT_MARGIN {
+        } else if y > LOGICAL_HEIGHT as i32 - MOVEMENT_MARGIN {
             MOVEMENT_SPEED
         } else {
             0
@@ -86,7 +179,14 @@ impl Editor {
     fn swap_selection(&mut self, state: &mut SharedState) {
         let scroll = state.controller.mouse().scroll();
 
-        if scroll > 0 {
+        if let Selection::Stamp(index) = self.selected {
+            let count = self.stamps.len();
+            if scroll > 0 {
+                self.selected = Selection::Stamp((index + 1) % count);
+            } else if scroll < 0 {
+                self.selected = Selection::Stamp((index + count - 1) % count);
+            }
+        } else if scroll > 0 {
             self.selected.set_to_next()
         } else if scroll < 0 {
             self.selected.set_to_prev()
@@ -98,6 +198,7 @@ impl Editor {
         if let Some(effect) = effect {
             match effect {
                 ButtonEffect::Menu => {
+                    state.resources.discard_recovery();
                     return ActivityResult::Exited;
                 },
                 ButtonEffect::Resume => {
@@ -105,7 +206,21 @@ impl Editor {
                     state.controller.clear_mouse();
                 },
                 ButtonEffect::Save => {
-                    state.resources.save_level(&self.level_name, &self.level);
+                    let (res, name) = (&mut state.resources, &self.level_name);
+                    self.warnings = match res.save_level(name, &self.level) {
+                        Ok(()) => {
+                            res.discard_recovery();
+                            self.saved = level_state(&self.level);
+                            self.autosaved = self.saved.clone();
+                            self.level
+                                .validate()
+                                .iter()
+                                .map(LevelIssue::to_string)
+                                .collect()
+                        },
+                        Err(err) => vec![err.to_string()],
+                    };
+                    self.warn_timer = WARNING_TIME;
                     self.paused = false;
                     state.controller.clear_mouse();
                 },
@@ -131,10 +246,14 @@ impl Editor {
     }
 
     fn set_selected(&mut self, pos: (usize, usize)) {
+        self.palette.remember(self.selected);
         match self.selected {
             Selection::Block(block) => {
                 self.level.set_block(pos, block);
             },
+            Selection::Terrain(terrain) => {
+                self.level.paint_terrain(pos, terrain);
+            },
             Selection::Background(bg) => {
                 self.level.set_bg(pos, bg);
             },
@@ -143,42 +262,50 @@ impl Editor {
                     self.level.fill_block(pos, collectible);
                 }
             },
-            Selection::Enemy(enemy) => {
-                let pos = (
-                    (pos.0 * BLOCK_SIZE as usize) as i32,
-                    (pos.1 * BLOCK_SIZE as usize) as i32,
-                );
-                let entity =
-                    EntityPrototype::new(EntityType::Enemy(enemy), pos);
-                self.level.insert_entity(entity);
+            Selection::Stamp(index) => {
+                self.level.paste(&self.stamps[index].1, pos);
             },
+            // Entities are placed by `update_entities`.
+            Selection::Enemy(..) | Selection::Warp => (),
         }
     }
 
+    // Hides the pointed block of the block layer, or shows it again.
+    fn toggle_hidden(&mut self, state: &SharedState) {
+        let pos = match (self.selected, self.cursor_block(state)) {
+            (Selection::Block(..), Some(pos)) => pos,
+            _ => return,
+        };
+        let mut block = self.level.get_block(pos);
+        block.toggle_hidden();
+        self.level.set_block(pos, block);
+    }
+
     fn free_selected(&mut self, pos: (usize, usize)) {
         match self.selected {
             Selection::Block(..) => {
                 self.level.set_block(pos, Block::default());
             },
+            Selection::Terrain(..) => {
+                self.level.erase_terrain(pos);
+            },
             Selection::Background(..) => {
                 self.level.set_bg(pos, BackgroundElement::default());
             },
             Selection::Collectible(..) => {
                 self.level.remove_block_contents(pos);
             },
-            Selection::Enemy(..) => {
-                let pos = (
-                    (pos.0 * BLOCK_SIZE as usize) as i32,
-                    (pos.1 * BLOCK_SIZE as usize) as i32,
-                );
-                self.level.remove_entity(pos);
-            },
+            Selection::Enemy(..) | Selection::Warp | Selection::Stamp(..) => (),
         }
     }
 
     fn copy_pointed(&mut self, pos: (usize, usize)) {
         let pointed = match self.selected {
             Selection::Block(..) => Selection::Block(self.level.get_block(pos)),
+            Selection::Terrain(..) => {
+                let kind = self.level.get_block(pos).kind();
+                Terrain::of(kind).map_or(self.selected, Selection::Terrain)
+            },
             Selection::Background(..) => {
                 Selection::Background(self.level.get_bg(pos))
             },
@@ -190,18 +317,363 @@ impl Editor {
                     self.selected
                 }
             },
-            Selection::Enemy(..) => {
-                let pos = (
-                    (pos.0 * BLOCK_SIZE as usize) as i32,
-                    (pos.1 * BLOCK_SIZE as usize) as i32,
-                );
-                Selection::Enemy(self.level.get_entity(pos))
+            Selection::Enemy(..) | Selection::Warp | Selection::Stamp(..) => {
+                self.selected
             },
         };
 
         self.selected = pointed;
     }
 
+    // Tools other than the pencil only make sense for the layers that fill
+    // the whole grid.
+    fn active_tool(&self) -> Tool {
+        match self.selected {
+            Selection::Block(..)
+            | Selection::Terrain(..)
+            | Selection::Background(..) => self.tool,
+            _ => Tool::Pencil,
+        }
+    }
+
+    // The left mouse button draws with the selected element, the right one
+    // erases.
+    fn apply_to_cells(&mut self, cells: &[(usize, usize)], button: MButton) {
+        for &cell in cells {
+            match button {
+                MButton::Left => self.set_selected(cell),
+                _ => self.free_selected(cell),
+            }
+        }
+    }
+
+    fn use_tool(&mut self, state: &mut SharedState) {
+        let controller = &state.controller;
+
+        if controller.was_key_pressed(Key::Tool) {
+            self.tool = self.tool.next();
+            self.shape = None;
+        } else if controller.was_key_pressed(Key::ShrinkBrush) {
+            self.brush_size = (self.brush_size - 1).max(1);
+        } else if controller.was_key_pressed(Key::GrowBrush) {
+            self.brush_size = (self.brush_size + 1).min(MAX_BRUSH_SIZE);
+        }
+
+        match self.active_tool() {
+            Tool::Pencil => self.modify_level(state),
+            Tool::Fill => self.fill(state),
+            Tool::Eraser => self.erase(state),
+            shape => self.drag_shape(shape, state),
+        }
+    }
+
+    fn fill(&mut self, state: &mut SharedState) {
+        let button = if state.controller.was_button_pressed(MButton::Left) {
+            MButton::Left
+        } else if state.controller.was_button_pressed(MButton::Right) {
+            MButton::Right
+        } else {
+            return;
+        };
+
+        let start = match self.cursor_block(state) {
+            Some(start) => start,
+            None => return,
+        };
+
+        let level = &self.level;
+        let cells = match self.selected {
+            Selection::Background(..) => {
+                let bg = level.get_bg(start);
+                flood_fill_cells(start, |pos| level.get_bg(pos) == bg)
+            },
+            _ => {
+                let block = level.get_block(start);
+                flood_fill_cells(start, |pos| level.get_block(pos) == block)
+            },
+        };
+
+        self.apply_to_cells(&cells, button);
+    }
+
+    fn erase(&mut self, state: &mut SharedState) {
+        let controller = &state.controller;
+        if !controller.is_button_active(MButton::Left)
+            && !controller.is_button_active(MButton::Right)
+        {
+            return;
+        }
+
+        if let Some(pos) = self.cursor_block(state) {
+            let cells = brush_cells(pos, self.brush_size);
+            self.apply_to_cells(&cells, MButton::Right);
+        }
+    }
+
+    // Shapes are previewed while dragging and drawn once the mouse button
+    // is released.
+    fn drag_shape(&mut self, tool: Tool, state: &mut SharedState) {
+        let controller = &state.controller;
+        let cursor = self.cursor_block(state);
+
+        if let Some(mut shape) = self.shape {
+            if controller.is_button_active(shape.button) {
+                shape.end = cursor.unwrap_or(shape.end);
+                self.shape = Some(shape);
+            } else {
+                self.shape = None;
+                let cells = tool.shape_cells(shape.start, shape.end);
+                self.apply_to_cells(&cells, shape.button);
+            }
+            return;
+        }
+
+        for &button in [MButton::Left, MButton::Right].iter() {
+            if let (true, Some(pos)) =
+                (controller.was_button_pressed(button), cursor)
+            {
+                self.shape = Some(ShapeDrag {
+                    start: pos,
+                    end: pos,
+                    button,
+                });
+            }
+        }
+    }
+
+    // Alt and the left mouse button select a rectangle of blocks, which can
+    // be copied, cut, moved, flipped, deleted or saved as a stamp.
+    fn update_region(&mut self, state: &mut SharedState) {
+        let controller = &state.controller;
+        let cursor = self.cursor_block(state);
+
+        if controller.is_key_active(Key::Alt)
+            && controller.was_button_pressed(MButton::Left)
+        {
+            self.drag_start = cursor;
+        }
+
+        if let Some(start) = self.drag_start {
+            if !controller.is_button_active(MButton::Left) {
+                self.drag_start = None;
+            } else if let Some(end) = cursor {
+                self.region = Some(Region::from_corners(start, end));
+            }
+        }
+
+        let ctrl = controller.is_key_active(Key::Ctrl);
+        if ctrl && controller.was_key_pressed(Key::Paste) {
+            self.floating = self.clipboard.clone();
+        }
+
+        // The floating stamp is flipped instead of the region under it.
+        let flip = controller.was_key_pressed(Key::Flip);
+        if let (true, Some(stamp)) = (flip, &self.floating) {
+            self.floating = Some(stamp.flipped(&state.resources));
+            return;
+        }
+
+        let region = match self.region {
+            Some(region) => region,
+            None => return,
+        };
+
+        if ctrl && controller.was_key_pressed(Key::Copy) {
+            self.clipboard = Some(self.level.copy_region(region));
+        } else if ctrl && controller.was_key_pressed(Key::Cut) {
+            self.clipboard = Some(self.level.copy_region(region));
+            self.level.clear_region(region);
+            self.unpick();
+            self.region = None;
+        } else if controller.was_key_pressed(Key::Move) {
+            self.floating = Some(self.level.copy_region(region));
+            self.level.clear_region(region);
+            self.unpick();
+            self.region = None;
+        } else if flip {
+            let stamp = self.level.copy_region(region);
+            self.level.clear_region(region);
+            self.unpick();
+            let flipped = stamp.flipped(&state.resources);
+            self.level.paste(&flipped, (region.x, region.y));
+        } else if controller.was_key_pressed(Key::Delete) {
+            self.level.clear_region(region);
+            self.unpick();
+        } else if controller.was_key_pressed(Key::Stamp) {
+            self.naming_stamp = true;
+            state.text_input.start();
+        }
+    }
+
+    // The floating stamp follows the cursor until it's placed with the left
+    // mouse button or dropped with the right one.
+    fn update_floating(&mut self, state: &mut SharedState) {
+        let controller = &state.controller;
+        let cursor = self.cursor_block(state);
+
+        if controller.was_button_pressed(MButton::Left) {
+            if let (Some(stamp), Some(pos)) = (&self.floating, cursor) {
+                self.level.paste(stamp, pos);
+                self.floating = None;
+                self.unpick();
+            }
+        } else if controller.was_button_pressed(MButton::Right) {
+            self.floating = None;
+        }
+    }
+
+    fn update_stamp_name(&mut self, state: &mut SharedState) {
+        if state.controller.was_key_pressed(Key::Escape) {
+            state.text_input.end();
+            self.naming_stamp = false;
+        } else if state.controller.was_key_pressed(Key::Enter) {
+            let name = state.text_input.end();
+            self.naming_stamp = false;
+
+            if let (false, Some(region)) = (name.is_empty(), self.region) {
+                let stamp = self.level.copy_region(region);
+                match state.resources.save_stamp(&name, &stamp) {
+                    Ok(()) => {
+                        self.stamps.retain(|(other, _)| *other != name);
+                        self.stamps.push((name, stamp));
+                        self.stamps.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    },
+                    Err(err) => {
+                        self.warnings = vec![err.to_string()];
+                        self.warn_timer = WARNING_TIME;
+                    },
+                }
+            }
+        }
+    }
+
+    fn pick_from_palette(&mut self, state: &mut SharedState) {
+        self.palette.scroll(&state.controller);
+        if let Some(selection) = self.palette.pick(&state.controller) {
+            self.selected = selection;
+            self.shape = None;
+        }
+    }
+
+    fn unpick(&mut self) {
+        self.picked = None;
+        self.drag_offset = None;
+    }
+
+    fn selected_entity(&self) -> Option<EntityType> {
+        match self.selected {
+            Selection::Enemy(enemy) => Some(EntityType::Enemy(enemy)),
+            Selection::Warp => Some(EntityType::Warp),
+            _ => None,
+        }
+    }
+
+    // Rounds a position to the closest corner of the grid if snapping is on.
+    fn snapped(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        if !self.snap {
+            return (x, y);
+        }
+
+        let size = BLOCK_SIZE as i32;
+        let round = |value: i32| (value + size / 2).div_euclid(size) * size;
+        (round(x), round(y))
+    }
+
+    // New entities are placed into the block under the cursor if snapping
+    // is on, otherwise they are centered on the cursor.
+    fn place_entity(&mut self, kind: EntityType, cursor: (i32, i32)) {
+        let mut entity = EntityPrototype::new(kind, cursor);
+        let hitbox = entity.hitbox();
+        entity.position = if self.snap {
+            let size = BLOCK_SIZE as i32;
+            (cursor.0.div_euclid(size) * size, cursor.1.div_euclid(size) * size)
+        } else {
+            (
+                cursor.0 - hitbox.width() as i32 / 2,
+                cursor.1 - hitbox.height() as i32 / 2,
+            )
+        };
+
+        self.level.insert_entity(entity);
+        self.palette.remember(self.selected);
+    }
+
+    // Entities are picked by their hitbox. The picked entity can be dragged
+    // around and its properties are shown in a panel.
+    fn update_entities(&mut self, kind: EntityType, state: &mut SharedState) {
+        let controller = &state.controller;
+        let cursor = self.camera.to_real_coords(controller.mouse().pos());
+
+        if let (Some(index), Some((off_x, off_y))) =
+            (self.picked, self.drag_offset)
+        {
+            if controller.is_button_active(MButton::Left) {
+                let pos = self.snapped((cursor.0 - off_x, cursor.1 - off_y));
+                self.level.entity_mut(index).position = pos;
+            } else {
+                self.drag_offset = None;
+            }
+            return;
+        }
+
+        let pointed = self.level.entity_at(cursor);
+        if controller.was_button_pressed(MButton::Left) {
+            let index = pointed.unwrap_or_else(|| {
+                self.place_entity(kind, cursor);
+                self.level.entities().len() - 1
+            });
+            let (x, y) = self.level.entities()[index].position;
+            self.picked = Some(index);
+            self.drag_offset = Some((cursor.0 - x, cursor.1 - y));
+        } else if controller.was_button_pressed(MButton::Right) {
+            let index = match pointed {
+                Some(index) => index,
+                None => return,
+            };
+
+            if !self.level.entities()[index].is_unique() {
+                self.level.remove_entity_at(index);
+                self.unpick();
+            }
+        } else if controller.was_button_pressed(MButton::Middle) {
+            let pointed = pointed.map(|index| self.level.entities()[index]);
+            match pointed.map(|entity| entity.kind) {
+                Some(EntityType::Enemy(enemy)) => {
+                    self.selected = Selection::Enemy(enemy);
+                },
+                Some(EntityType::Warp) => self.selected = Selection::Warp,
+                _ => (),
+            }
+        }
+    }
+
+    fn properties_panel(&self) -> Option<PropertiesPanel> {
+        let entity = *self.level.entities().get(self.picked?)?;
+        if Property::of(entity.kind).is_empty() {
+            return None;
+        }
+
+        let right_edge = if self.palette.is_visible() {
+            LOGICAL_WIDTH - PALETTE_WIDTH
+        } else {
+            LOGICAL_WIDTH
+        };
+        Some(PropertiesPanel::new(entity, right_edge as i32))
+    }
+
+    // The left mouse button changes the clicked property to the next value,
+    // the right one to the previous value.
+    fn change_property(&mut self, property: Property, state: &SharedState) {
+        let controller = &state.controller;
+        let forward = controller.was_button_pressed(MButton::Left);
+        let backward = controller.was_button_pressed(MButton::Right);
+
+        if let (Some(index), true) = (self.picked, forward || backward) {
+            let properties = &mut self.level.entity_mut(index).properties;
+            property.change(properties, forward);
+        }
+    }
+
     fn modify_level(&mut self, state: &mut SharedState) {
         fn is_proper_input(ctrl: &Controller, button: MButton) -> bool {
             ctrl.was_button_pressed(button)
@@ -226,7 +698,84 @@ impl Editor {
         }
     }
 
+    // Changes are written to the recovery file from time to time, so that
+    // they aren't lost when the game crashes.
+    fn autosave(&mut self, state: &SharedState) {
+        self.autosave += 1;
+        if self.autosave < AUTOSAVE_TIME {
+            return;
+        }
+        self.autosave = 0;
+
+        let current = level_state(&self.level);
+        if current == self.autosaved {
+            return;
+        }
+
+        // changes that were undone leave nothing to recover
+        let recovery = Recovery::new(&self.level_name, &self.level);
+        let result = if current == self.saved {
+            state.resources.discard_recovery();
+            Ok(())
+        } else {
+            state.resources.save_recovery(&recovery)
+        };
+
+        match result {
+            Ok(()) => self.autosaved = current,
+            Err(err) => {
+                self.warnings = vec![format!("Autosave failed: {}", err)];
+                self.warn_timer = WARNING_TIME;
+            },
+        }
+    }
+
+    // The level is reloaded when its file is changed by another program,
+    // unless there are unsaved changes. The camera stays where it was.
+    pub fn reload(&mut self, changes: &ResourceChanges, res: &ResourceManager) {
+        if !changes.level_changed(&self.level_name) {
+            return;
+        }
+
+        let current = level_state(&self.level);
+        if current != self.saved && !changes.forced {
+            self.warnings = vec![format!(
+                "Level '{}' was changed by another program, press F5 to \
+                 reload it and lose the unsaved changes",
+                self.level_name
+            )];
+            self.warn_timer = WARNING_TIME;
+            return;
+        }
+
+        match res.load_level(&self.level_name) {
+            // the level was saved by the editor itself
+            Ok(level) if level_state(&level) == current => (),
+            Ok(level) => {
+                self.saved = level_state(&level);
+                self.autosaved = self.saved.clone();
+                self.level = level;
+                self.unpick();
+                self.region = None;
+                res.discard_recovery();
+            },
+            Err(err) if err.is_missing_file() => (),
+            Err(err) => {
+                self.warnings = vec![err.to_string()];
+                self.warn_timer = WARNING_TIME;
+            },
+        }
+    }
+
     pub fn update(&mut self, state: &mut SharedState) -> ActivityResult {
+        self.warn_timer = self.warn_timer.saturating_sub(1);
+        self.autosave(state);
+
+        if self.naming_stamp {
+            self.update_stamp_name(state);
+            return ActivityResult::Active;
+        }
+
         if state.controller.was_key_pressed(Key::Escape) {
             self.paused ^= true;
         }
@@ -234,17 +783,68 @@ impl Editor {
         if self.paused {
             self.update_menu(state)
         } else {
-            self.move_camera(state);
+            let mouse_pos = state.controller.mouse().pos();
+            let over_palette = self.palette.is_hovered(mouse_pos);
+            let over_property = self
+                .properties_panel()
+                .and_then(|panel| panel.property_at(mouse_pos));
+            if !over_palette && over_property.is_none() {
+                self.move_camera(state);
+            }
+
             if state.controller.was_key_pressed(Key::Left) {
                 self.level.theme = self.level.theme.prev();
             } else if state.controller.was_key_pressed(Key::Right) {
                 self.level.theme = self.level.theme.next();
             } else if state.controller.was_key_pressed(Key::Tab) {
                 self.selected.switch_layer();
+                self.shape = None;
+                if let (Selection::Stamp(..), true) =
+                    (self.selected, self.stamps.is_empty())
+                {
+                    self.selected.switch_layer();
+                }
+            } else if state.controller.was_key_pressed(Key::Preview) {
+                self.parallax ^= true;
+            } else if state.controller.was_key_pressed(Key::Palette) {
+                self.palette.toggle();
+            } else if state.controller.was_key_pressed(Key::Snap) {
+                self.snap ^= true;
+            } else if state.controller.was_key_pressed(Key::Hide) {
+                self.toggle_hidden(state);
+            } else if state.controller.was_key_pressed(Key::RaiseWater) {
+                self.level.raise_water();
+            } else if state.controller.was_key_pressed(Key::LowerWater) {
+                self.level.lower_water();
+            }
+
+            if over_palette {
+                self.pick_from_palette(state);
+                return ActivityResult::Active;
+            }
+
+            if let Some(property) = over_property {
+                self.change_property(property, state);
+                return ActivityResult::Active;
+            }
+
+            if self.selected_entity().is_none() {
+                self.unpick();
             }
 
             self.swap_selection(state);
-            self.modify_level(state);
+            self.update_region(state);
+
+            if self.floating.is_some() {
+                self.update_floating(state);
+            } else if self.drag_start.is_none()
+                && !state.controller.is_key_active(Key::Alt)
+            {
+                match self.selected_entity() {
+                    Some(kind) => self.update_entities(kind, state),
+                    None => self.use_tool(state),
+                }
+            }
             ActivityResult::Active
         }
     }
@@ -265,6 +865,13 @@ impl Editor {
                 };
                 call.draw_with(&block, renderer).show(&mut state.resources);
             },
+            Selection::Terrain(terrain) => {
+                let block = ThemedBlock {
+                    block: Block::from(terrain.icon()),
+                    theme: self.level.theme,
+                };
+                call.draw_with(&block, renderer).show(&mut state.resources);
+            },
             Selection::Background(background) => {
                 let themed_bg = ThemedBackgroundElement {
                     element: background,
@@ -282,10 +889,153 @@ impl Editor {
                     EntityPrototype::new(EntityType::Enemy(enemy), pos);
                 call.draw_with(&entity, renderer).show(&mut state.resources);
             },
+            Selection::Warp => {
+                let entity = EntityPrototype::new(EntityType::Warp, pos);
+                call.draw_with(&entity, renderer).show(&mut state.resources);
+            },
+            Selection::Stamp(index) => {
+                self.draw_stamp(&self.stamps[index].1, renderer, state);
+            },
+        }
+    }
+
+    // Stamps are previewed in full size, snapped to the block under
+    // the cursor.
+    fn draw_stamp(
+        &self,
+        stamp: &Stamp,
+        renderer: &mut Renderer,
+        state: &mut SharedState,
+    ) {
+        if let Some((x, y)) = self.cursor_block(state) {
+            let pos = (
+                (x * BLOCK_SIZE as usize) as i32,
+                (y * BLOCK_SIZE as usize) as i32,
+            );
+            let stamp = ThemedStamp {
+                stamp,
+                theme: self.level.theme,
+            };
+            renderer
+                .draw(&stamp)
+                .position(pos)
+                .camera(self.camera)
+                .tick(state.frame)
+                .mode(DrawMode::Editor)
+                .show(&mut state.resources);
         }
     }
 
+    fn draw_cells(&self, renderer: &mut Renderer, cells: &[(usize, usize)]) {
+        renderer.canvas.set_draw_color(Color::RGBA(255, 255, 255, 96));
+        for &(x, y) in cells {
+            let mut rect = Block::hitbox(x, y);
+            self.camera.move_rect(&mut rect);
+            renderer.canvas.fill_rect(rect).unwrap();
+        }
+    }
+
+    fn draw_tool(&self, renderer: &mut Renderer, state: &mut SharedState) {
+        const MARGIN: i32 = 10;
+
+        let tool = self.active_tool();
+        let cursor = self.cursor_block(state);
+        if let Some(shape) = self.shape {
+            let cells = tool.shape_cells(shape.start, shape.end);
+            self.draw_cells(renderer, &cells);
+        } else if let (Tool::Eraser, Some(pos)) = (tool, cursor) {
+            self.draw_cells(renderer, &brush_cells(pos, self.brush_size));
+        }
+
+        let label = match tool {
+            Tool::Eraser => format!("{} {}", tool.name(), self.brush_size),
+            tool => String::from(tool.name()),
+        };
+        let label = match self.selected {
+            Selection::Terrain(terrain) => {
+                format!("{} {}", terrain.name(), label)
+            },
+            Selection::Enemy(..) | Selection::Warp if self.snap => {
+                String::from("SNAP TO GRID")
+            },
+            Selection::Enemy(..) | Selection::Warp => {
+                String::from("FREE PLACEMENT")
+            },
+            _ => label,
+        };
+        let (_, height) = renderer.logical_size();
+        renderer
+            .draw(&text!(&label))
+            .position((MARGIN, height as i32 - MARGIN - 32))
+            .scale(0.25)
+            .show(&mut state.resources);
+    }
+
+    fn draw_picked(&self, renderer: &mut Renderer, state: &mut SharedState) {
+        let entity = match self.picked {
+            Some(index) => self.level.entities()[index],
+            None => return,
+        };
+
+        let mut rect = entity.editor_hitbox();
+        self.camera.move_rect(&mut rect);
+        renderer.canvas.set_draw_color(Color::RGB(255, 255, 0));
+        renderer.canvas.draw_rect(rect).unwrap();
+
+        if let Some(panel) = self.properties_panel() {
+            renderer.draw(&panel).show(&mut state.resources);
+        }
+    }
+
+    fn draw_region(&self, renderer: &mut Renderer) {
+        if let Some(region) = self.region {
+            let mut rect = region.hitbox();
+            self.camera.move_rect(&mut rect);
+            renderer.canvas.set_draw_color(Color::RGBA(255, 255, 255, 64));
+            renderer.canvas.fill_rect(rect).unwrap();
+            renderer.canvas.set_draw_color(Color::RGB(255, 255, 255));
+            renderer.canvas.draw_rect(rect).unwrap();
+        }
+    }
+
+    fn draw_stamp_prompt(
+        &self,
+        renderer: &mut Renderer,
+        state: &mut SharedState,
+    ) {
+        renderer.fill(Color::RGBA(0, 0, 0, 192));
+        let prompt = centered_text!("Stamp name: ");
+        let input = centered_text!(state.text_input.text());
+        let center = renderer.center();
+
+        renderer
+            .draw(&prompt)
+            .position(center)
+            .shift((0, -100))
+            .scale(0.2)
+            .show(&mut state.resources);
+
+        renderer
+            .draw(&input)
+            .position(center)
+            .scale(0.25)
+            .show(&mut state.resources);
+    }
+
     pub fn draw(&self, renderer: &mut Renderer, state: &mut SharedState) {
+        renderer.clear(Color::from(self.level.theme));
+
+        if self.parallax {
+            let parallax = ThemedParallax {
+                theme: self.level.theme,
+            };
+            renderer
+                .draw(&parallax)
+                .camera(self.camera)
+                .tick(state.frame)
+                .show(&mut state.resources);
+        }
+
         renderer
             .draw(&self.level)
             .mode(DrawMode::Editor)
@@ -301,22 +1051,81 @@ impl Editor {
                 .show(&mut state.resources);
         } else {
             draw_grid(renderer, self.camera);
-            self.draw_selected(renderer, state);
+            self.draw_region(renderer);
+            self.draw_tool(renderer, state);
+
+            let palette = ThemedPalette {
+                palette:  &self.palette,
+                selected: self.selected,
+                theme:    self.level.theme,
+            };
+            renderer
+                .draw(&palette)
+                .tick(state.frame)
+                .show(&mut state.resources);
+
+            self.draw_picked(renderer, state);
+
+            let mouse_pos = state.controller.mouse().pos();
+            let over_panel = self
+                .properties_panel()
+                .and_then(|panel| panel.property_at(mouse_pos))
+                .is_some();
+            if !self.palette.is_hovered(mouse_pos) && !over_panel {
+                match &self.floating {
+                    Some(stamp) => self.draw_stamp(stamp, renderer, state),
+                    None => self.draw_selected(renderer, state),
+                }
+            }
+        }
+
+        if self.naming_stamp {
+            self.draw_stamp_prompt(renderer, state);
+        }
+
+        self.draw_warnings(renderer, state);
+    }
+
+    fn draw_warnings(&self, renderer: &mut Renderer, state: &mut SharedState) {
+        const MARGIN: i32 = 10;
+        const LINE_HEIGHT: i32 = 24;
+
+        if self.warn_timer == 0 {
+            return;
+        }
+
+        for (i, warning) in self.warnings.iter().enumerate() {
+            let text = TextBuilder::new(warning)
+                .color(Color::RGB(255, 220, 0))
+                .build();
+            renderer
+                .draw(&text)
+                .position((MARGIN, MARGIN + i as i32 * LINE_HEIGHT))
+                .scale(0.25)
+                .show(&mut state.resources);
         }
     }
 }
 
+// Serialized form of the level, compared to tell whether it has changed.
+fn level_state(level: &Level) -> String {
+    serde_json::to_string(&LevelJSON::from(level)).unwrap_or_default()
+}
+
 impl Selection {
     pub fn switch_layer(&mut self) {
         let new = match self {
-            Selection::Block(..) => {
+            Selection::Block(..) => Selection::Terrain(Terrain::Ground),
+            Selection::Terrain(..) => {
                 Selection::Background(BackgroundElement::default_visible())
             },
             Selection::Background(..) => {
                 Selection::Collectible(Collectible::Coins(1))
             },
             Selection::Collectible(..) => Selection::Enemy(EnemyType::Goomba),
-            Selection::Enemy(..) => Selection::Block(Block::default_visible()),
+            Selection::Enemy(..) => Selection::Warp,
+            Selection::Warp => Selection::Stamp(0),
+            Selection::Stamp(..) => Selection::Block(Block::default_visible()),
         };
 
         *self = new
@@ -333,18 +1142,24 @@ impl Selection {
     pub fn next(self) -> Self {
         match self {
             Selection::Block(block) => Selection::Block(block.next_kind()),
+            Selection::Terrain(terrain) => Selection::Terrain(terrain.next()),
             Selection::Background(bg) => Selection::Background(bg.next()),
             Selection::Collectible(c) => Selection::Collectible(c.next()),
             Selection::Enemy(enemy) => Selection::Enemy(enemy.next()),
+            Selection::Warp => Selection::Warp,
+            Selection::Stamp(index) => Selection::Stamp(index),
         }
     }
 
     pub fn prev(self) -> Self {
         match self {
             Selection::Block(block) => Selection::Block(block.prev_kind()),
+            Selection::Terrain(terrain) => Selection::Terrain(terrain.prev()),
             Selection::Background(bg) => Selection::Background(bg.prev()),
             Selection::Collectible(c) => Selection::Collectible(c.prev()),
             Selection::Enemy(enemy) => Selection::Enemy(enemy.prev()),
+            Selection::Warp => Selection::Warp,
+            Selection::Stamp(index) => Selection::Stamp(index),
         }
     }
 }
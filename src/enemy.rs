@@ -1,3 +1,4 @@
+use crate::animation::*;
 use crate::render::*;
 use crate::resource::*;
 use crate::texture_id::*;
@@ -13,24 +14,54 @@ use num_traits::FromPrimitive;
 #[derive(Debug)]
 #[derive(Deserialize, Serialize)]
 #[derive(Copy, Clone)]
+#[derive(PartialEq, Eq)]
 #[derive(FromPrimitive)]
 #[repr(u8)]
 pub enum EnemyType {
     Goomba,
     Koopa,
-    /* FlyingKoopa,
-     * PiranhaPlant,
-     * EmptyShell,
-     * BuzzyBeetle,
-     * Spiny,
-     * HammerBro, */
+    Blooper,
+    CheepCheep,
+    FlyingKoopa,
+    PiranhaPlant,
+    EmptyShell,
+    BuzzyBeetle,
+    Spiny,
+    HammerBro,
 }
 
 pub const ENEMY_KILL_BOUNCE : f64 = -10.0;
 
-const MAX_EDITOR_SELECTION: u8 = EnemyType::Koopa as u8;
+const MAX_EDITOR_SELECTION: u8 = EnemyType::HammerBro as u8;
 
 pub const GOOMBA_ACCELERATION: f64 = 0.4;
+pub const CHEEP_CHEEP_SPEED: f64 = 1.5;
+pub const BLOOPER_SINK_SPEED: f64 = 1.0;
+pub const BLOOPER_DASH_SPEED: f64 = -5.0;
+pub const BLOOPER_DASH_DRIFT: f64 = 2.5;
+pub const BLOOPER_DASH_DECAY: f64 = 0.94;
+pub const PARATROOPA_HOP: f64 = -14.0;
+pub const SHELL_SPEED: f64 = 10.0;
+// Frames after a kick during which the shell doesn't hurt the player.
+pub const SHELL_KICK_GRACE: u16 = 12;
+
+// Piranha plants stay out of their pipe, sink into it, stay hidden and
+// rise again, this many frames each.
+pub const PIRANHA_OUT_TIME: u16 = 90;
+pub const PIRANHA_MOVE_TIME: u16 = 48;
+pub const PIRANHA_HIDDEN_TIME: u16 = 90;
+// Hidden piranha plants don't come out while the player is closer to them
+// than this.
+pub const PIRANHA_SHY_DISTANCE: i32 = 96;
+
+pub const HAMMER_BRO_ACCELERATION: f64 = 0.3;
+// Frames between changes of the pacing direction, jumps and hammer throws.
+pub const HAMMER_BRO_PACE_TIME: u16 = 60;
+pub const HAMMER_BRO_JUMP_TIME: u16 = 150;
+pub const HAMMER_BRO_THROW_TIME: u16 = 70;
+pub const HAMMER_BRO_JUMP: f64 = -16.0;
+pub const HAMMER_SPEED_X: f64 = 4.0;
+pub const HAMMER_SPEED_Y: f64 = -12.0;
 
 impl EnemyType {
     pub fn prev(self) -> Self {
@@ -52,8 +83,54 @@ impl EnemyType {
         match self {
             EnemyType::Goomba => TextureId::EnemyGoomba,
             EnemyType::Koopa => TextureId::EnemyKoopa,
+            EnemyType::Blooper => TextureId::EnemyBlooper,
+            EnemyType::CheepCheep => TextureId::EnemyCheepCheep,
+            EnemyType::FlyingKoopa => TextureId::EnemyFlyingKoopa,
+            EnemyType::PiranhaPlant => TextureId::EnemyPiranhaPlant,
+            EnemyType::EmptyShell => TextureId::EnemyShell,
+            EnemyType::BuzzyBeetle => TextureId::EnemyBuzzyBeetle,
+            EnemyType::Spiny => TextureId::EnemySpiny,
+            EnemyType::HammerBro => TextureId::EnemyHammerBro,
         }
     }
+
+    pub fn sprite(self) -> Sprite {
+        match self {
+            EnemyType::Goomba => Sprite::Goomba,
+            EnemyType::Koopa => Sprite::Koopa,
+            EnemyType::Blooper => Sprite::Blooper,
+            EnemyType::CheepCheep => Sprite::CheepCheep,
+            EnemyType::FlyingKoopa => Sprite::FlyingKoopa,
+            EnemyType::PiranhaPlant => Sprite::PiranhaPlant,
+            EnemyType::EmptyShell => Sprite::Shell,
+            EnemyType::BuzzyBeetle => Sprite::BuzzyBeetle,
+            EnemyType::Spiny => Sprite::Spiny,
+            EnemyType::HammerBro => Sprite::HammerBro,
+        }
+    }
+
+    // Jumping on these hurts the player.
+    pub fn is_stompable(self) -> bool {
+        !matches!(
+            self,
+            EnemyType::Spiny
+                | EnemyType::PiranhaPlant
+                | EnemyType::Blooper
+                | EnemyType::CheepCheep
+        )
+    }
+
+    // Fireballs of the flower power won't hurt these. Nothing checks it
+    // yet, the fire flower isn't in the game.
+    pub fn is_fire_immune(self) -> bool {
+        matches!(self, EnemyType::BuzzyBeetle)
+    }
+
+    // Piranha plants are drawn behind the blocks, so they can hide in
+    // their pipes.
+    pub fn hides_behind_blocks(self) -> bool {
+        matches!(self, EnemyType::PiranhaPlant)
+    }
 }
 
 impl Drawable for EnemyType {
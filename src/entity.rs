@@ -1,12 +1,16 @@
+use crate::animation::*;
 use crate::block::*;
 use crate::enemy::*;
 use crate::hitbox::*;
 use crate::level::*;
 use crate::physics::*;
+use crate::player::*;
 use crate::render::*;
 use crate::resource::*;
 use crate::texture_id::*;
+use crate::utility::*;
 
+use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::Texture;
 
@@ -19,16 +23,43 @@ use std::rc::Rc;
 #[derive(Debug)]
 #[derive(Copy, Clone)]
 pub struct Entity {
-    pub kind: EntityType,
-    pub body: PhysicalBody,
+    pub kind:        EntityType,
+    pub body:        PhysicalBody,
+    pub properties:  EntityProperties,
+    pub animation:   Option<Animation>,
+    // Knocked off entities fall out of the level through the blocks.
+    pub knocked_off: bool,
+    // Frames counted by enemies that act periodically.
+    pub timer:       u16,
+    // Enemies knocked off by a sliding shell since it was kicked.
+    pub combo:       u8,
 }
 
 #[derive(Serialize, Deserialize)]
 #[derive(Copy, Clone)]
 #[derive(Debug)]
 pub struct EntityPrototype {
-    pub kind:     EntityType,
-    pub position: (i32, i32),
+    pub kind:       EntityType,
+    pub position:   (i32, i32),
+    #[serde(default)]
+    pub properties: EntityProperties,
+}
+
+// Parameters of a single entity set in the editor. Not every kind of entity
+// uses all of them.
+#[derive(Serialize, Deserialize)]
+#[derive(Copy, Clone)]
+#[derive(Debug, PartialEq)]
+pub struct EntityProperties {
+    // Enemies placed with `Still` pick a random direction.
+    #[serde(default = "default_direction")]
+    pub direction: XDirection,
+    // Multiplier of the usual walking speed.
+    #[serde(default = "default_speed")]
+    pub speed:     f64,
+    // Warps with the same link lead to each other.
+    #[serde(default)]
+    pub warp:      Option<u8>,
 }
 
 #[derive(Debug)]
@@ -37,53 +68,50 @@ pub struct EntityPrototype {
 pub enum EntityType {
     Collectible(Collectible),
     Enemy(EnemyType),
-    Particle(Particle),
     EndFlag,
+    PlayerSpawn,
+    Warp,
+    // Grows out of a bumped block, the player can climb it.
+    Vine,
+    // Thrown by hammer bros, flies through the blocks.
+    Hammer,
     Dead,
 }
 
-#[derive(Debug)]
-#[derive(Serialize, Deserialize)]
-#[derive(Copy, Clone)]
-pub enum Particle {
-    Coin { lifetime: u8 },
-    BlockFragment { kind: BlockType, theme: LevelTheme },
-}
-
 pub const STAR_ACCEL: f64 = 0.9;
 pub const STAR_JUMP: f64 = -20.0;
 pub const MUSHROOM_ACCEL: f64 = 0.7;
+pub const VINE_GROWTH: i32 = 2;
 
-impl Particle {
-    pub fn new_coin() -> Self {
-        const LIFETIME: u8 = 25;
-        Particle::Coin { lifetime: LIFETIME }
-    }
+pub const MIN_ENTITY_SPEED: f64 = 0.25;
+pub const MAX_ENTITY_SPEED: f64 = 3.0;
+pub const MAX_WARP_LINK: u8 = 9;
 
-    pub fn new_fragment(kind: BlockType, theme: LevelTheme) -> Self {
-        Particle::BlockFragment { kind, theme }
-    }
+fn default_direction() -> XDirection {
+    XDirection::Still
+}
 
-    pub fn update(self, i: usize, world: &mut PlayableLevel) {
-        let mut body = world.entities[i].body;
-        body.accelerate(vec2d!(0.0, 0.0));
-        body.apply_movement_unchecked();
-        world.entities[i].body = body;
+fn default_speed() -> f64 {
+    1.0
+}
 
-        let updated = match self {
-            Particle::Coin { lifetime } => {
-                if lifetime > 0 {
-                    Particle::Coin {
-                        lifetime: lifetime - 1,
-                    }
-                } else {
-                    self
-                }
-            },
-            particle => particle,
-        };
+impl Default for EntityProperties {
+    fn default() -> Self {
+        EntityProperties {
+            direction: default_direction(),
+            speed:     default_speed(),
+            warp:      None,
+        }
+    }
+}
 
-        world.entities[i].kind = EntityType::Particle(updated);
+impl EntityType {
+    pub fn sprite(self) -> Option<Sprite> {
+        match self {
+            EntityType::Enemy(enemy) => Some(enemy.sprite()),
+            EntityType::Hammer => Some(Sprite::Hammer),
+            _ => None,
+        }
     }
 }
 
@@ -92,9 +120,28 @@ impl EntityPrototype {
         EntityPrototype {
             kind,
             position: pos,
+            properties: EntityProperties::default(),
         }
     }
 
+    // The end flag only collides with its pole in the editor, the rest of
+    // its hitbox spans the whole height of the level.
+    pub fn editor_hitbox(self) -> Hitbox {
+        let (x, y) = self.position;
+        match self.kind {
+            EntityType::EndFlag => {
+                let height = LEVEL_HEIGHT as i32 * BLOCK_SIZE as i32 - y;
+                Hitbox::new(x, y, 128, height.max(1) as u32)
+            },
+            _ => self.hitbox(),
+        }
+    }
+
+    // Every level has exactly one of these, so they can only be moved.
+    pub fn is_unique(self) -> bool {
+        matches!(self.kind, EntityType::EndFlag | EntityType::PlayerSpawn)
+    }
+
     pub fn hitbox(self) -> Hitbox {
         let (x, y) = self.position;
         match self.kind {
@@ -105,13 +152,28 @@ impl EntityPrototype {
                 match enemy {
                     EnemyType::Goomba => Hitbox::new(x, y, 64, 64),
                     EnemyType::Koopa => Hitbox::new(x, y, 48, 81),
+                    EnemyType::FlyingKoopa => Hitbox::new(x, y, 48, 81),
+                    EnemyType::PiranhaPlant | EnemyType::HammerBro => {
+                        Hitbox::new(x, y, 64, 96)
+                    },
+                    EnemyType::Blooper
+                    | EnemyType::CheepCheep
+                    | EnemyType::EmptyShell
+                    | EnemyType::BuzzyBeetle
+                    | EnemyType::Spiny => Hitbox::new(x, y, 64, 64),
                 }
             },
-            EntityType::Particle(particle) => Hitbox::new(x, y, 1, 1),
             EntityType::Dead => Hitbox::new(-100, -100, 1, 1),
+            EntityType::Hammer => Hitbox::new(x, y, 32, 32),
             EntityType::EndFlag => {
                 Hitbox::new(x, 0, 128, LEVEL_HEIGHT as u32 * BLOCK_SIZE)
             },
+            EntityType::PlayerSpawn => {
+                Hitbox::new(x, y, PLAYER_WIDTH, PLAYER_HEIGHT)
+            },
+            EntityType::Warp | EntityType::Vine => {
+                Hitbox::new(x, y, BLOCK_SIZE, BLOCK_SIZE)
+            },
         }
     }
 
@@ -129,13 +191,24 @@ impl From<EntityPrototype> for Entity {
         Entity {
             kind: prototype.kind,
             body,
+            properties: prototype.properties,
+            animation: prototype.kind.sprite().map(|sprite| {
+                Animation::new(sprite, Clip::Walk)
+            }),
+            knocked_off: false,
+            timer: 0,
+            combo: 0,
         }
     }
 }
 
 impl From<&Entity> for EntityPrototype {
     fn from(entity: &Entity) -> EntityPrototype {
-        EntityPrototype::new(entity.kind, entity.body.position())
+        EntityPrototype {
+            kind:       entity.kind,
+            position:   entity.body.position(),
+            properties: entity.properties,
+        }
     }
 }
 
@@ -149,6 +222,11 @@ impl Entity {
         Entity {
             kind: EntityType::Dead,
             body,
+            properties: EntityProperties::default(),
+            animation: None,
+            knocked_off: false,
+            timer: 0,
+            combo: 0,
         }
     }
 
@@ -168,58 +246,269 @@ impl Entity {
         entity
     }
 
-    pub fn spawn_coin(pos: (usize, usize)) -> Entity {
-        Self::spawn_with_speed(
-            EntityType::Particle(Particle::new_coin()),
-            pos,
-            vec2d!(0.0, -10.0),
-        )
+    // Dying entities stop moving and are removed once their death clip
+    // ends.
+    pub fn die(&mut self) {
+        if let Some(animation) = self.animation.as_mut() {
+            animation.play(Clip::Death);
+        } else {
+            *self = Entity::dead();
+        }
+    }
+
+    // Hit by a bumped block or the player with the star power, the entity
+    // jumps up upside down.
+    pub fn knock_off(&mut self) {
+        const KNOCK_OFF_JUMP: f64 = -12.0;
+        self.knocked_off = true;
+        self.body.stop_x();
+        self.body.stop_y();
+        self.body.grounded = false;
+        self.body.accelerate(vec2d!(0.0, KNOCK_OFF_JUMP));
+        if let Some(animation) = self.animation.as_mut() {
+            animation.turn_upside_down();
+        }
     }
 
-    pub fn is_dead(&self) -> bool {
+    pub fn fall(&mut self) {
+        self.body.grounded = false;
+        self.body.accelerate(vec2d!(0.0, 0.0));
+        self.body.apply_movement_unchecked();
+    }
+
+    pub fn hides_behind_blocks(&self) -> bool {
         match self.kind {
-            EntityType::Dead => true,
-            EntityType::Particle(Particle::Coin { lifetime }) => lifetime == 0,
+            EntityType::Enemy(enemy) => enemy.hides_behind_blocks(),
             _ => false,
         }
     }
-}
 
-impl Drawable for Particle {
-    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
-        match data.object {
-            Particle::Coin { .. } => {
-                let coin = Collectible::Coins(1);
-                pass_draw!(data, &coin).show(res);
+    // Walking enemies start in the direction set in the editor and turn
+    // around at walls, paratroopas hop along.
+    pub fn walk(&mut self, world: &mut PlayableLevel) {
+        let mut body = self.body;
+        let accel = GOOMBA_ACCELERATION * self.properties.speed;
+        match (body.x_direction(), self.properties.direction) {
+            (XDirection::Still, XDirection::Left) => {
+                body.accelerate(vec2d!(-accel, 0.0));
             },
-            Particle::BlockFragment { kind, theme } => {
-                let info = res.block_texture_info(*kind);
+            (XDirection::Still, XDirection::Right) => {
+                body.accelerate(vec2d!(accel, 0.0));
+            },
+            _ => body.accelerate_or_bounce(accel, world),
+        }
+        if body.grounded
+            && matches!(self.kind, EntityType::Enemy(EnemyType::FlyingKoopa))
+        {
+            body.accelerate(vec2d!(0.0, PARATROOPA_HOP));
+        }
+        body.apply_movement(world, false);
+        self.body = body;
+    }
 
-                const SIZE: u32 = 13;
-                let (x, y) = data.position;
-                let x = x + BLOCK_SIZE as i32 / 2 - (SIZE / 2) as i32;
-                let y = y + BLOCK_SIZE as i32 / 2 - (SIZE / 2) as i32;
+    // Stomped enemies die, lose their wings or retreat into their shells,
+    // stomped shells stop.
+    pub fn stomped(&mut self) {
+        match self.kind {
+            EntityType::Enemy(EnemyType::FlyingKoopa) => {
+                self.transform(EnemyType::Koopa);
+            },
+            EntityType::Enemy(EnemyType::Koopa)
+            | EntityType::Enemy(EnemyType::BuzzyBeetle) => {
+                self.transform(EnemyType::EmptyShell);
+                self.properties.direction = XDirection::Still;
+            },
+            EntityType::Enemy(EnemyType::EmptyShell) => {
+                self.properties.direction = XDirection::Still;
+            },
+            _ => self.die(),
+        }
+    }
 
-                if !data.camera.in_view(rect!(x, y, SIZE, SIZE)) {
-                    return;
-                }
+    // Changes into another enemy standing on the same spot.
+    fn transform(&mut self, enemy: EnemyType) {
+        let (x, bottom) = (self.body.hitbox.x(), self.body.hitbox.bottom());
+        let mut prototype =
+            EntityPrototype::new(EntityType::Enemy(enemy), (x, 0));
+        prototype.properties = self.properties;
+        prototype.position.1 = bottom - prototype.hitbox().height() as i32;
+        *self = Entity::from(prototype);
+    }
 
-                let sprite_y =
-                    (info.variant_index(*theme) * info.height) as i32;
+    // Shells lie still until they are kicked, then they slide along the
+    // ground and bounce off walls.
+    pub fn slide(&mut self, world: &mut PlayableLevel) {
+        self.timer = self.timer.saturating_sub(1);
+        let speed = match self.properties.direction {
+            XDirection::Left => -SHELL_SPEED,
+            XDirection::Right => SHELL_SPEED,
+            XDirection::Still => 0.0,
+        };
+        if let Some(animation) = self.animation.as_mut() {
+            let clip = if speed == 0.0 { Clip::Idle } else { Clip::ShellSpin };
+            animation.play(clip);
+        }
 
-                let src_region = rect!(0, sprite_y, SIZE, SIZE);
+        self.body.move_at(vec2d!(speed, self.body.speed_y()));
+        self.body.accelerate(vec2d!(0.0, 0.0));
+        self.body.apply_movement(world, false);
+        if speed != 0.0 && self.body.speed_x() == 0.0 {
+            self.properties.direction = match self.properties.direction {
+                XDirection::Left => XDirection::Right,
+                _ => XDirection::Left,
+            };
+        }
+    }
 
-                let (cam_x, cam_y) = data.camera.translate_coords((x, y));
-                let dest = rect!(cam_x, cam_y, SIZE, SIZE);
+    pub fn kick(&mut self, direction: XDirection) {
+        self.properties.direction = direction;
+        self.timer = SHELL_KICK_GRACE;
+        self.combo = 0;
+    }
 
-                let path = info.path.clone();
+    pub fn is_sliding(&self) -> bool {
+        self.properties.direction != XDirection::Still
+    }
 
-                data.renderer
-                    .canvas
-                    .copy(&res.texture(&path), src_region, dest)
-                    .expect("Failed to draw a particle!");
-            },
+    // Piranha plants rise out of their pipe and hide in it again, they
+    // don't come out while the player is next to the pipe.
+    pub fn lurk(&mut self, player: Hitbox) {
+        let height = self.body.hitbox.height() as i32;
+        let depth = piranha_depth(self.timer, height);
+        let distance = player.center().x() - self.body.hitbox.center().x();
+        if depth == height && distance.abs() < PIRANHA_SHY_DISTANCE {
+            return;
+        }
+
+        let period =
+            PIRANHA_OUT_TIME + PIRANHA_MOVE_TIME * 2 + PIRANHA_HIDDEN_TIME;
+        self.timer = (self.timer + 1) % period;
+        self.body
+            .move_by((0, piranha_depth(self.timer, height) - depth));
+    }
+
+    // Hammer bros pace back and forth, jump now and then and throw hammers
+    // at the player.
+    pub fn brawl(
+        &mut self,
+        world: &mut PlayableLevel,
+        player: Hitbox,
+    ) -> Option<Entity> {
+        self.timer = self.timer.wrapping_add(1);
+        let pace = if (self.timer / HAMMER_BRO_PACE_TIME).is_multiple_of(2) {
+            -HAMMER_BRO_ACCELERATION
+        } else {
+            HAMMER_BRO_ACCELERATION
+        };
+        let jump = if self.body.grounded
+            && self.timer.is_multiple_of(HAMMER_BRO_JUMP_TIME)
+        {
+            HAMMER_BRO_JUMP
+        } else {
+            0.0
+        };
+        self.body
+            .accelerate(vec2d!(pace * self.properties.speed, jump));
+        self.body.apply_movement(world, false);
+
+        if !self.timer.is_multiple_of(HAMMER_BRO_THROW_TIME) {
+            return None;
         }
+        let hitbox = self.body.hitbox;
+        let side = if player.center().x() < hitbox.center().x() {
+            -1.0
+        } else {
+            1.0
+        };
+        let mut hammer =
+            Entity::new(EntityType::Hammer, (hitbox.center().x(), hitbox.y()));
+        hammer
+            .body
+            .move_at(vec2d!(side * HAMMER_SPEED_X, HAMMER_SPEED_Y));
+        Some(hammer)
+    }
+
+    // Vines grow upwards from their block until they reach the top of the
+    // level.
+    pub fn grow(&mut self) {
+        let hitbox = &mut self.body.hitbox;
+        let growth = VINE_GROWTH.min(hitbox.top());
+        hitbox.set_y(hitbox.y() - growth);
+        hitbox.set_height(hitbox.height() + growth as u32);
+    }
+
+    // Cheep-cheeps swim back and forth, turning around at walls and at the
+    // edge of the water.
+    pub fn swim(&mut self, world: &mut PlayableLevel) {
+        let heading = match self.properties.direction {
+            XDirection::Still if rand::random() => XDirection::Left,
+            XDirection::Still => XDirection::Right,
+            heading => heading,
+        };
+        let speed = CHEEP_CHEEP_SPEED * self.properties.speed;
+        let speed = if heading == XDirection::Left {
+            -speed
+        } else {
+            speed
+        };
+
+        let before = self.body.hitbox;
+        self.body.move_at(vec2d!(speed, 0.0));
+        self.body.apply_movement(world, false);
+        let blocked = self.body.speed_x() == 0.0;
+        if blocked || !world.is_water(self.body.hitbox.center()) {
+            self.body.hitbox = before;
+            self.properties.direction = match heading {
+                XDirection::Left => XDirection::Right,
+                _ => XDirection::Left,
+            };
+        } else {
+            self.properties.direction = heading;
+        }
+    }
+
+    // Bloopers sink slowly and dash upwards towards the player whenever
+    // the player is above them.
+    pub fn chase(&mut self, world: &mut PlayableLevel, target: Hitbox) {
+        let multiplier = self.properties.speed;
+        let speed = self.body.speed();
+        let speed = if speed.y < -BLOOPER_SINK_SPEED * multiplier {
+            speed * BLOOPER_DASH_DECAY
+        } else if target.bottom() < self.body.hitbox.top() {
+            let side = if target.center().x() < self.body.hitbox.center().x()
+            {
+                -1.0
+            } else {
+                1.0
+            };
+            vec2d!(side * BLOOPER_DASH_DRIFT, BLOOPER_DASH_SPEED) * multiplier
+        } else {
+            vec2d!(0.0, BLOOPER_SINK_SPEED * multiplier)
+        };
+
+        let before = self.body.hitbox;
+        self.body.move_at(speed);
+        self.body.apply_movement(world, false);
+        if !world.is_water(self.body.hitbox.center()) {
+            self.body.hitbox = before;
+            self.body.move_at(vec2d!(0.0, BLOOPER_SINK_SPEED * multiplier));
+        }
+    }
+
+    pub fn is_dying(&self) -> bool {
+        self.animation
+            .is_some_and(|animation| animation.clip() == Clip::Death)
+    }
+
+    pub fn update_animation(
+        &mut self,
+        res: &ResourceManager,
+    ) -> Option<AnimationEvent> {
+        self.animation.as_mut()?.update(res)
+    }
+
+    pub fn is_dead(&self) -> bool {
+        matches!(self.kind, EntityType::Dead)
     }
 }
 
@@ -231,16 +520,39 @@ impl Drawable for EntityPrototype {
                     .position(data.object.position)
                     .show(res);
             },
-            EntityType::Particle(particle) => {
-                pass_draw!(data, &particle)
-                    .position(data.object.position)
-                    .show(res);
-            },
             EntityType::Enemy(enemy) => {
+                // Enemies facing right are flipped in the editor.
+                let mode = match (data.mode, data.object.properties.direction) {
+                    (DrawMode::Editor, XDirection::Right) => {
+                        DrawMode::EntityDirection(XDirection::Right)
+                    },
+                    (mode, _) => mode,
+                };
                 pass_draw!(data, &enemy)
                     .position(data.object.position)
+                    .mode(mode)
                     .show(res);
             },
+            EntityType::PlayerSpawn => {
+                if matches!(
+                    data.mode,
+                    DrawMode::Editor
+                        | DrawMode::EditorSelection
+                        | DrawMode::Standard
+                ) {
+                    show_spawn(data, res);
+                }
+            },
+            EntityType::Warp => {
+                if matches!(
+                    data.mode,
+                    DrawMode::Editor
+                        | DrawMode::EditorSelection
+                        | DrawMode::Standard
+                ) {
+                    show_warp(data, res);
+                }
+            },
             EntityType::EndFlag => {
                 let (x, y) = data.object.position;
                 let info = res.entity_texture_info(TextureId::Flag);
@@ -266,12 +578,120 @@ impl Drawable for EntityPrototype {
     }
 }
 
+// The spawn point is shown as the standing player in the editor.
+fn show_spawn(data: DrawCall<EntityPrototype>, res: &mut ResourceManager) {
+    let info = res.entity_texture_info(TextureId::PlayerStanding);
+    let (x, y) = data.object.position;
+    let (off_x, off_y) = info.hitbox_offset();
+    let (x, y) = data.camera.translate_coords((x + off_x, y + off_y));
+
+    let width = (info.width as f64 * data.scale) as u32;
+    let height = (info.height as f64 * data.scale) as u32;
+    let src_region = rect!(0, 0, info.width, info.height);
+    let dest = rect!(x, y, width, height);
+
+    let path = info.path.clone();
+    data.renderer
+        .canvas
+        .copy(&res.texture(&path), src_region, dest)
+        .expect("Failed to draw the player spawn!");
+}
+
+// Warps are invisible in the game, the editor shows them as a box with
+// the link they belong to.
+fn show_warp(data: DrawCall<EntityPrototype>, res: &mut ResourceManager) {
+    let (x, y) = data.camera.translate_coords(data.object.position);
+    let size = (f64::from(BLOCK_SIZE) * data.scale) as u32;
+    let rect = rect!(x, y, size, size);
+    data.renderer.canvas.set_draw_color(Color::RGBA(160, 0, 255, 128));
+    data.renderer
+        .canvas
+        .fill_rect(rect)
+        .expect("Failed to draw a warp!");
+
+    let link = match data.object.properties.warp {
+        Some(link) => link.to_string(),
+        None => String::from("-"),
+    };
+    let text = TextBuilder::new(&link)
+        .alignment(TextAlignment::TotalCenter)
+        .build();
+    data.renderer
+        .draw(&text)
+        .position((rect.center().x(), rect.center().y()))
+        .scale(0.3 * data.scale)
+        .show(res);
+}
+
+// How far a piranha plant is down in its pipe at the given point of its
+// cycle.
+fn piranha_depth(timer: u16, height: i32) -> i32 {
+    let moving = i32::from(PIRANHA_MOVE_TIME);
+    let sinking = i32::from(PIRANHA_OUT_TIME);
+    let hidden = sinking + moving;
+    let rising = hidden + i32::from(PIRANHA_HIDDEN_TIME);
+    let timer = i32::from(timer);
+    if timer < sinking {
+        0
+    } else if timer < hidden {
+        height * (timer - sinking) / moving
+    } else if timer < rising {
+        height
+    } else {
+        height * (rising + moving - timer) / moving
+    }
+}
+
+// Vines are a column of vine textures, the topmost one cut to the height
+// the vine has grown to.
+fn show_vine(data: DrawCall<Entity>, res: &mut ResourceManager) {
+    let hitbox = data.object.body.hitbox;
+    if !data.camera.in_view(hitbox) {
+        return;
+    }
+
+    let info = res.entity_texture_info(TextureId::CollectibleVine);
+    let (width, piece) = (info.width, info.height as i32);
+    let path = info.path.clone();
+    let mut bottom = hitbox.bottom();
+    while bottom > hitbox.top() {
+        let height = piece.min(bottom - hitbox.top());
+        let src_region = rect!(0, piece - height, width, height);
+        let y = bottom - height;
+        let (cam_x, cam_y) = data.camera.translate_coords((hitbox.x(), y));
+        let dest = rect!(cam_x, cam_y, width, height);
+        data.renderer
+            .canvas
+            .copy(&res.texture(&path), src_region, dest)
+            .expect("Failed to draw a vine!");
+        bottom -= height;
+    }
+}
+
 impl Drawable for Entity {
     fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
-        let prototype = EntityPrototype::from(data.object);
-        pass_draw!(data, &prototype)
-            .position(data.object.body.position())
-            .mode(DrawMode::EntityDirection(data.object.body.x_direction()))
-            .show(res);
+        let entity = data.object;
+        if let EntityType::Vine = entity.kind {
+            show_vine(data, res);
+            return;
+        }
+
+        let position = entity.body.position();
+        let mode = DrawMode::EntityDirection(entity.body.x_direction());
+        match &entity.animation {
+            Some(animation) => {
+                pass_draw!(data, animation)
+                    .position(position)
+                    .mode(mode)
+                    .show(res);
+            },
+            None => {
+                let prototype = EntityPrototype::from(entity);
+                pass_draw!(data, &prototype)
+                    .position(position)
+                    .mode(mode)
+                    .show(res);
+            },
+        }
     }
 }
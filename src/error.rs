@@ -0,0 +1,162 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type ResourceResult<T> = Result<T, ResourceError>;
+
+// Reasons why a resource couldn't be loaded or saved. Unlike the message
+// boxes these are handed to the caller, which decides whether the game can
+// go on without the resource.
+#[derive(Debug)]
+pub enum ResourceError {
+    MissingFile(PathBuf),
+    Io {
+        path:   PathBuf,
+        source: io::Error,
+    },
+    Write {
+        path:   PathBuf,
+        source: io::Error,
+    },
+    Serialize {
+        path:   PathBuf,
+        source: serde_json::Error,
+    },
+    Json {
+        path:    PathBuf,
+        line:    usize,
+        column:  usize,
+        message: String,
+    },
+    UnknownId {
+        path:   PathBuf,
+        line:   usize,
+        column: usize,
+        id:     String,
+    },
+    LevelSize {
+        expected: usize,
+        found:    usize,
+    },
+    Texture {
+        name:    String,
+        message: String,
+    },
+    NoLevels(String),
+}
+
+impl ResourceError {
+    pub fn io(path: &Path, source: io::Error) -> ResourceError {
+        match source.kind() {
+            io::ErrorKind::NotFound => {
+                ResourceError::MissingFile(path.to_path_buf())
+            },
+            _ => ResourceError::Io {
+                path: path.to_path_buf(),
+                source,
+            },
+        }
+    }
+
+    // Names that don't match any variant of an enum, e.g. a block type
+    // removed from the game, are reported on their own.
+    pub fn json(path: &Path, source: serde_json::Error) -> ResourceError {
+        let path = path.to_path_buf();
+        let (line, column) = (source.line(), source.column());
+        let message = source.to_string();
+
+        match unknown_variant(&message) {
+            Some(id) => ResourceError::UnknownId {
+                path,
+                line,
+                column,
+                id,
+            },
+            None => ResourceError::Json {
+                path,
+                line,
+                column,
+                message,
+            },
+        }
+    }
+
+    pub fn is_missing_file(&self) -> bool {
+        matches!(self, ResourceError::MissingFile(..))
+    }
+}
+
+// serde_json reports unknown variants as "unknown variant `Name`, ...".
+fn unknown_variant(message: &str) -> Option<String> {
+    let rest = message.strip_prefix("unknown variant `")?;
+    let end = rest.find('`')?;
+    Some(String::from(&rest[..end]))
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResourceError::MissingFile(path) => {
+                write!(f, "File {} doesn't exist", path.display())
+            },
+            ResourceError::Io { path, source } => {
+                write!(f, "Failed to access {} ({})", path.display(), source)
+            },
+            ResourceError::Write { path, source } => {
+                write!(f, "Failed to write {} ({})", path.display(), source)
+            },
+            ResourceError::Serialize { path, source } => {
+                write!(f, "Failed to serialize {} ({})", path.display(), source)
+            },
+            ResourceError::Json {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Error in JSON file {} at line {}, column {}: {}",
+                path.display(),
+                line,
+                column,
+                message
+            ),
+            ResourceError::UnknownId {
+                path,
+                line,
+                column,
+                id,
+            } => write!(
+                f,
+                "Unknown id '{}' in {} at line {}, column {}",
+                id,
+                path.display(),
+                line,
+                column
+            ),
+            ResourceError::LevelSize { expected, found } => write!(
+                f,
+                "Corrupted level: expected {} blocks, found {}",
+                expected, found
+            ),
+            ResourceError::Texture { name, message } => {
+                write!(f, "Failed to load texture {} ({})", name, message)
+            },
+            ResourceError::NoLevels(pack) => {
+                write!(f, "No playable levels in the level pack '{}'", pack)
+            },
+        }
+    }
+}
+
+impl Error for ResourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ResourceError::Io { source, .. } => Some(source),
+            ResourceError::Write { source, .. } => Some(source),
+            ResourceError::Serialize { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
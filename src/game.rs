@@ -1,10 +1,14 @@
+use crate::animation::*;
+use crate::audio::*;
 use crate::block::*;
 use crate::controller::*;
 use crate::enemy::*;
 use crate::entity::*;
+use crate::error::*;
 use crate::hitbox::*;
 use crate::interface::*;
 use crate::level::*;
+use crate::particle::*;
 use crate::player::*;
 use crate::render::*;
 use crate::resource::*;
@@ -13,12 +17,15 @@ use crate::utility::*;
 
 use sdl2::pixels::Color;
 
+use std::fmt;
+
 pub struct Game {
     camera:     Camera,
     player:     Player,
     score:      Score,
     level_info: LevelInfo,
     level:      PlayableLevel,
+    particles:  ParticleSystem,
     state:      State,
     menu:       ButtonColumn<ButtonEffect>,
 }
@@ -47,50 +54,97 @@ enum ButtonEffect {
 
 #[derive(Clone, Copy)]
 pub struct Score {
-    lives: u8,
-    coins: u8,
+    lives:  u8,
+    coins:  u8,
+    points: u32,
+}
+
+// What a kill is worth, the longer a combo gets the more it pays.
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum Reward {
+    Points(u32),
+    ExtraLife,
 }
 
+// Kills in a row past the end of the list award an extra life each.
+const COMBO_POINTS: [u32; 10] =
+    [100, 200, 400, 500, 800, 1000, 2000, 4000, 5000, 8000];
+
 const BUMP_FALLOFF: u8 = 3;
 const BUMP_FORCE: u8 = BUMP_FALLOFF * 8;
 
+// Frames between two puffs of dust while skidding, or two sparkles of the
+// star power.
+const SKID_DUST_INTERVAL: u32 = 4;
+const SPARKLE_INTERVAL: u32 = 6;
+
 impl Game {
     fn new_level_loading_screen() -> State {
         State::LevelLoading(LOADING_SCREEN_TIME)
     }
 
+    // A level that got broken since it was started is restarted from the
+    // copy loaded at that time.
     fn restart(&mut self, state: &mut SharedState) {
-        self.player = Player::default();
+        self.level = self
+            .level_info
+            .load_level(&state.resources)
+            .unwrap_or_else(|_| self.level.prototype.clone().into());
+        self.player = Player::spawn(&self.level.prototype);
         self.player.stick_camera(&mut self.camera);
-        self.level = self.level_info.load_level(&state.resources);
         self.score = Score::new();
+        self.particles.clear();
+        state.jukebox.stop();
     }
 
-    fn next_level(&mut self, state: &mut SharedState) {
-        let variant = self.player.variant;
-        self.player = Player::default();
-        self.player.variant = variant;
-        self.player.stick_camera(&mut self.camera);
+    // The current level is replaced when its file changes, the player keeps
+    // its position so that a level can be tweaked while playing it.
+    pub fn reload(&mut self, changes: &ResourceChanges, res: &ResourceManager) {
+        if !changes.level_changed(self.level_info.current_name()) {
+            return;
+        }
 
+        match self.level_info.load_level(res) {
+            Ok(level) => self.level = level,
+            Err(err) => eprintln!("Failed to reload the level: {}", err),
+        }
+    }
+
+    fn next_level(&mut self, state: &mut SharedState) {
         match self.level_info.next_level(&state.resources) {
             Some(level) => self.level = level,
-            None => self.state = State::GameFinished,
+            None => {
+                self.state = State::GameFinished;
+                return;
+            },
         }
+
+        let variant = self.player.variant;
+        self.player = Player::spawn(&self.level.prototype);
+        self.player.variant = variant;
+        self.player.stick_camera(&mut self.camera);
+        self.particles.clear();
+        state.jukebox.stop();
     }
 
-    pub fn new(res: &ResourceManager) -> Game {
-        let player = Player::default();
-        let camera = Camera::new(player.rect().x(), player.rect().y());
+    pub fn new(
+        res: &ResourceManager,
+        start: Option<&str>,
+    ) -> ResourceResult<Game> {
+        let mut level_info = LevelInfo::new(res, start)?;
+        let level = level_info.load_playable(res)?;
+
+        let player = Player::spawn(&level.prototype);
+        let mut camera = Camera::new(player.rect().x(), player.rect().y());
+        player.stick_camera(&mut camera);
         let buttons = ButtonColumnBuilder::new()
             .add(("RESUME", ButtonEffect::Resume))
             .add(("RESTART", ButtonEffect::Restart))
             .add(("MENU", ButtonEffect::Menu))
             .build();
 
-        let level_info = LevelInfo::new(res);
-        let level = level_info.load_level(res);
-
-        Game {
+        Ok(Game {
             player,
             camera,
             score: Score::new(),
@@ -98,6 +152,18 @@ impl Game {
             menu: buttons,
             level_info,
             level,
+            particles: ParticleSystem::new(),
+        })
+    }
+
+    // The music is paused together with the game.
+    fn set_paused(&mut self, paused: bool, state: &mut SharedState) {
+        if paused {
+            self.state = State::Paused;
+            state.jukebox.pause();
+        } else {
+            self.state = State::Running;
+            state.jukebox.resume();
         }
     }
 
@@ -105,7 +171,7 @@ impl Game {
         match self.menu.effect_if_clicked(&state.controller) {
             Some(ButtonEffect::Menu) => ActivityResult::Exited,
             Some(ButtonEffect::Resume) => {
-                self.state = State::Running;
+                self.set_paused(false, state);
                 state.controller.clear_mouse();
                 ActivityResult::Active
             },
@@ -119,12 +185,92 @@ impl Game {
     }
 
     fn update_player(&mut self, state: &mut SharedState) {
-        self.player.accelerate(&state.controller);
+        self.player.update_animation(&state.controller, &state.resources);
+        let vine = self.touched_vine();
+        self.player.grab_vine(&state.controller, vine);
+        match vine {
+            Some(vine) if self.player.climbing => {
+                self.player.climb(&state.controller, vine);
+            },
+            _ if self.player.body.submerged => {
+                let hitbox = self.player.body.hitbox;
+                let head = hitbox
+                    .top_left()
+                    .offset(hitbox.width() as i32 / 2, 0);
+                let surface = !self.level.is_water(head);
+                self.player.swim(&state.controller, surface);
+            },
+            _ => self.player.accelerate(&state.controller),
+        }
+        let airborne = !self.player.body.grounded;
         self.player.apply_movement(&mut self.level);
+        if self.player.body.grounded {
+            self.player.combo = 0;
+        }
         self.player.stick_camera(&mut self.camera);
         if self.player.invincibility > 0 {
             self.player.invincibility -= 1;
         }
+        if self.player.star_power > 0 {
+            self.player.star_power -= 1;
+            if self.player.star_power == 0 {
+                state.jukebox.stop();
+            }
+        }
+        self.emit_player_particles(airborne, state);
+    }
+
+    // The vine in front of the center of the player.
+    fn touched_vine(&self) -> Option<Hitbox> {
+        let center = self.player.body.hitbox.center();
+        self.level
+            .entities
+            .iter()
+            .filter(|entity| matches!(entity.kind, EntityType::Vine))
+            .map(|entity| entity.body.hitbox)
+            .find(|vine| vine.contains_point(center))
+    }
+
+    fn emit_player_particles(&mut self, airborne: bool, state: &SharedState) {
+        let res = &state.resources;
+        let hitbox = self.player.body.hitbox;
+        let feet = (hitbox.center().x(), hitbox.bottom());
+
+        if airborne && self.player.body.grounded {
+            self.particles.emit(res, Emitter::LandingDust, feet);
+        }
+        if self.player.animation.clip() == Clip::Skid
+            && state.frame.is_multiple_of(SKID_DUST_INTERVAL)
+        {
+            self.particles.emit(res, Emitter::SkidDust, feet);
+        }
+        if self.player.has_star_power()
+            && state.frame.is_multiple_of(SPARKLE_INTERVAL)
+        {
+            let center = (hitbox.center().x(), hitbox.center().y());
+            self.particles.emit(res, Emitter::StarSparkle, center);
+        }
+    }
+
+    // Adds the reward to the score and shows it above the killed entity.
+    fn award(&mut self, reward: Reward, i: usize, state: &SharedState) {
+        self.score.add(reward);
+
+        let hitbox = self.level.entities[i].body.hitbox;
+        let position = (hitbox.center().x(), hitbox.top());
+        self.particles.emit_from(
+            &state.resources,
+            Emitter::ScorePopup,
+            position,
+            ParticleSource::Text(reward.to_string()),
+        );
+    }
+
+    fn emit_stomp_puff(&mut self, i: usize, state: &SharedState) {
+        let center = self.level.entities[i].body.hitbox.center();
+        let position = (center.x(), center.y());
+        self.particles
+            .emit(&state.resources, Emitter::StompPuff, position);
     }
 
     fn bump_entities(
@@ -142,8 +288,8 @@ impl Game {
             if self.level.entities[i].body.hitbox.collides(&bump_hitbox) {
                 match self.level.entities[i].kind {
                     EntityType::Enemy(..) => {
-                        // TODO: add particle
-                        self.level.entities[i] = Entity::dead();
+                        self.award(Reward::combo(0), i, state);
+                        self.level.entities[i].knock_off();
                     },
                     EntityType::Collectible(Collectible::Coins(..)) => (),
                     EntityType::Collectible(..) => {
@@ -163,47 +309,66 @@ impl Game {
 
         if real_block.block.is_empty() {
             if self.player.is_big() {
-                real_block.spawn_particles(
+                let source = ParticleSource::Block(
+                    real_block.block.kind(),
                     self.level.prototype.theme,
-                    (x, y),
-                    &mut self.level.entities,
+                );
+                let center = (
+                    (x as u32 * BLOCK_SIZE + BLOCK_SIZE / 2) as i32,
+                    (y as u32 * BLOCK_SIZE + BLOCK_SIZE / 2) as i32,
                 );
                 real_block.block = Block::default();
+                self.particles.emit_from(
+                    &state.resources,
+                    Emitter::BlockFragments,
+                    center,
+                    source,
+                );
             }
             return;
         }
 
         match real_block.block.get_contents() {
-            Some(Collectible::Coins(num)) => {
+            Some(Collectible::Coins(..)) | Some(Collectible::MultiCoin) => {
                 self.score.coins += 1;
                 if self.score.coins == 100 {
                     self.score.coins = 0;
                     self.score.lives += 1;
                 }
 
-                let coin = Entity::spawn_coin((x, y - 1));
-                self.level.entities.push(coin);
-            },
-            Some(Collectible::Mushroom) => {
-                let entity = Entity::spawn(
-                    EntityType::Collectible(Collectible::Mushroom),
-                    (x, y - 1),
+                let position = (
+                    (x as u32 * BLOCK_SIZE) as i32,
+                    ((y - 1) as u32 * BLOCK_SIZE) as i32,
                 );
+                self.particles
+                    .emit(&state.resources, Emitter::Coin, position);
+            },
+            Some(item @ Collectible::Mushroom)
+            | Some(item @ Collectible::OneUp)
+            | Some(item @ Collectible::Star) => {
+                let entity =
+                    Entity::spawn(EntityType::Collectible(item), (x, y - 1));
 
                 self.level.entities.push(entity);
             },
-            Some(Collectible::Star) => {
-                let entity = Entity::spawn(
-                    EntityType::Collectible(Collectible::Star),
-                    (x, y - 1),
-                );
-
+            Some(Collectible::Vine) => {
+                let entity = Entity::spawn(EntityType::Vine, (x, y - 1));
                 self.level.entities.push(entity);
             },
             _ => (),
         }
 
-        real_block.block.delete_item();
+        // The timer of a multi-coin block starts with its first bump, the
+        // first bump after it runs out pays the last coin.
+        let paid_out = match real_block.block.get_contents() {
+            Some(Collectible::MultiCoin) => {
+                *real_block.coin_timer.get_or_insert(MULTI_COIN_TIME) == 0
+            },
+            _ => true,
+        };
+        if paid_out {
+            real_block.block.delete_item();
+        }
 
         if real_block.block.is_empty() {
             real_block.block.set_kind(BlockType::QuestionMarkEmpty);
@@ -226,17 +391,47 @@ impl Game {
                 };
 
                 self.level.blocks[y][x].state = new_state;
+                if let Some(timer) = self.level.blocks[y][x].coin_timer.as_mut()
+                {
+                    *timer = timer.saturating_sub(1);
+                }
             }
         }
     }
 
     fn too_far(player: &Player, entity: &Entity) -> bool {
-        const MARGIN: i32 = BLOCK_SIZE as i32 * 5 + SCREEN_WIDTH as i32 / 2;
+        const MARGIN: i32 = BLOCK_SIZE as i32 * 5 + LOGICAL_WIDTH as i32 / 2;
         let (player_x, _) = player.position();
         let (entity_x, _) = entity.body.position();
         (entity_x - player_x).abs() > MARGIN
     }
 
+    // Moves the player to the other warp with the same link, standing on
+    // its bottom edge.
+    fn warp(&mut self, from: usize) {
+        let link = match self.level.entities[from].properties.warp {
+            Some(link) => link,
+            None => return,
+        };
+
+        let target = self.level.entities.iter().enumerate().find(|(i, e)| {
+            *i != from
+                && matches!(e.kind, EntityType::Warp)
+                && e.properties.warp == Some(link)
+        });
+
+        if let Some((_, target)) = target {
+            let hitbox = target.body.hitbox;
+            let player = &mut self.player.body;
+            let (x, y) = player.position();
+            let target_y = hitbox.bottom() - player.hitbox.height() as i32;
+            player.move_by((hitbox.x() - x, target_y - y));
+            player.stop_x();
+            player.stop_y();
+            self.player.climbing = false;
+        }
+    }
+
     fn update_entities(&mut self, state: &mut SharedState) {
         let len = self.level.entities.len();
         for i in 0..len {
@@ -244,13 +439,38 @@ impl Game {
                 continue;
             }
 
+            let event =
+                self.level.entities[i].update_animation(&state.resources);
+            if event == Some(AnimationEvent::Finished(Clip::Death)) {
+                self.level.entities[i] = Entity::dead();
+                continue;
+            }
+            if self.level.entities[i].is_dying() {
+                continue;
+            }
+            if self.level.entities[i].knocked_off {
+                self.level.entities[i].fall();
+                continue;
+            }
+            if self.star_power_kill(i, state) {
+                continue;
+            }
+
             match self.level.entities[i].kind {
-                EntityType::Particle(particle) => {
-                    particle.update(i, &mut self.level);
-                },
                 EntityType::Collectible(Collectible::Flower) => {
                     unimplemented!();
                 },
+                EntityType::Collectible(Collectible::OneUp) => {
+                    let mut body = self.level.entities[i].body;
+                    if body.hitbox.collides(&self.player.body.hitbox) {
+                        self.award(Reward::ExtraLife, i, state);
+                        self.level.entities[i] = Entity::dead();
+                        continue;
+                    }
+                    body.accelerate_or_bounce(MUSHROOM_ACCEL, &self.level);
+                    body.apply_movement(&mut self.level, false);
+                    self.level.entities[i].body = body;
+                },
                 EntityType::Collectible(Collectible::Mushroom) => {
                     let mut body = self.level.entities[i].body;
                     if body.hitbox.collides(&self.player.body.hitbox) {
@@ -269,7 +489,8 @@ impl Game {
                 EntityType::Collectible(Collectible::Star) => {
                     let mut body = self.level.entities[i].body;
                     if body.hitbox.collides(&self.player.body.hitbox) {
-                        self.player.invincibility = INVINCIBILITY_TIME;
+                        self.player.start_star_power();
+                        state.jukebox.play(Track::StarPower);
                         self.level.entities[i] = Entity::dead();
                         continue;
                     }
@@ -286,40 +507,124 @@ impl Game {
                     body.apply_movement(&mut self.level, false);
                     self.level.entities[i].body = body;
                 },
-                EntityType::Enemy(EnemyType::Goomba) => {
-                    let mut body = self.level.entities[i].body;
-                    if body.hitbox.collides(&self.player.body.hitbox) {
-                        if self.player.body.speed_y() > 0.0
-                            || self.player.invincibility > 0
+                EntityType::Enemy(enemy @ EnemyType::Goomba)
+                | EntityType::Enemy(enemy @ EnemyType::Koopa)
+                | EntityType::Enemy(enemy @ EnemyType::FlyingKoopa)
+                | EntityType::Enemy(enemy @ EnemyType::BuzzyBeetle)
+                | EntityType::Enemy(enemy @ EnemyType::Spiny) => {
+                    let hitbox = self.level.entities[i].body.hitbox;
+                    if hitbox.collides(&self.player.body.hitbox) {
+                        if enemy.is_stompable()
+                            && self.player.body.speed_y() > 0.0
                         {
-                            self.level.entities[i] = Entity::dead();
-                            self.player.body.accelerate(vec2d!(
-                                0.0,
-                                ENEMY_KILL_BOUNCE - self.player.body.speed_y()
-                            ));
-                            // TODO: spawn particle
+                            self.stomp(i, state);
                             continue;
-                        } else {
-                            // TODO: display death animation
-                            if self.score.lives <= 1 {
-                                self.state = State::GameOver;
-                                return;
+                        } else if self.hurt_player(state) {
+                            return;
+                        }
+                    }
+
+                    let mut entity = self.level.entities[i];
+                    entity.walk(&mut self.level);
+                    self.level.entities[i] = entity;
+                },
+                // Still shells are kicked away from the player, sliding ones
+                // knock off the enemies in their way.
+                EntityType::Enemy(EnemyType::EmptyShell) => {
+                    let entity = self.level.entities[i];
+                    let hitbox = entity.body.hitbox;
+                    if hitbox.collides(&self.player.body.hitbox) {
+                        let player_x = self.player.body.hitbox.center().x();
+                        if !entity.is_sliding() {
+                            let direction = if player_x < hitbox.center().x() {
+                                XDirection::Right
                             } else {
-                                let prev_score = self.score;
-                                self.restart(state);
-                                self.score = prev_score;
-                                self.score.lives -= 1;
-                                self.state =
-                                    State::LevelLoading(LOADING_SCREEN_TIME);
-                                return;
-                            }
+                                XDirection::Left
+                            };
+                            self.level.entities[i].kick(direction);
+                        } else if self.player.body.speed_y() > 0.0 {
+                            self.stomp(i, state);
+                        } else if entity.timer == 0 && self.hurt_player(state)
+                        {
+                            return;
                         }
                     }
 
-                    body.accelerate_or_bounce(GOOMBA_ACCELERATION, &self.level);
-                    body.apply_movement(&mut self.level, false);
-                    self.level.entities[i].body = body;
+                    let mut entity = self.level.entities[i];
+                    entity.slide(&mut self.level);
+                    self.level.entities[i] = entity;
+                    if entity.is_sliding() {
+                        self.shell_hits(i, state);
+                    }
+                },
+                EntityType::Enemy(EnemyType::PiranhaPlant) => {
+                    let hitbox = self.level.entities[i].body.hitbox;
+                    if hitbox.collides(&self.player.body.hitbox)
+                        && self.hurt_player(state)
+                    {
+                        return;
+                    }
+
+                    self.level.entities[i].lurk(self.player.body.hitbox);
+                },
+                EntityType::Enemy(EnemyType::HammerBro) => {
+                    let hitbox = self.level.entities[i].body.hitbox;
+                    if hitbox.collides(&self.player.body.hitbox) {
+                        if self.player.body.speed_y() > 0.0 {
+                            self.stomp(i, state);
+                            continue;
+                        } else if self.hurt_player(state) {
+                            return;
+                        }
+                    }
+
+                    let mut entity = self.level.entities[i];
+                    let hammer =
+                        entity.brawl(&mut self.level, self.player.body.hitbox);
+                    self.level.entities[i] = entity;
+                    if let Some(hammer) = hammer {
+                        self.level.entities.push(hammer);
+                    }
+                },
+                EntityType::Hammer => {
+                    let hitbox = self.level.entities[i].body.hitbox;
+                    if hitbox.collides(&self.player.body.hitbox)
+                        && self.hurt_player(state)
+                    {
+                        return;
+                    }
+
+                    self.level.entities[i].fall();
                 },
+                // Swimming enemies can't be stomped.
+                EntityType::Enemy(enemy @ EnemyType::Blooper)
+                | EntityType::Enemy(enemy @ EnemyType::CheepCheep) => {
+                    let hitbox = self.level.entities[i].body.hitbox;
+                    if hitbox.collides(&self.player.body.hitbox)
+                        && self.hurt_player(state)
+                    {
+                        return;
+                    }
+
+                    let mut entity = self.level.entities[i];
+                    if enemy == EnemyType::Blooper {
+                        entity.chase(&mut self.level, self.player.body.hitbox);
+                    } else {
+                        entity.swim(&mut self.level);
+                    }
+                    self.level.entities[i] = entity;
+                },
+                // Warps are entered with down, or by climbing into them.
+                EntityType::Warp => {
+                    let hitbox = self.level.entities[i].body.hitbox;
+                    if hitbox.collides(&self.player.body.hitbox)
+                        && (state.controller.was_key_pressed(Key::Down)
+                            || self.player.climbing)
+                    {
+                        self.warp(i);
+                    }
+                },
+                EntityType::Vine => self.level.entities[i].grow(),
                 EntityType::EndFlag => {
                     let hitbox = self.level.entities[i].body.hitbox;
                     if hitbox.collides(&self.player.body.hitbox) {
@@ -337,12 +642,91 @@ impl Game {
             .retain(|entity| !entity.body.out_of_bounds() && !entity.is_dead())
     }
 
+    // Returns whether the player died, which ends the update of the
+    // entities.
+    fn hurt_player(&mut self, state: &mut SharedState) -> bool {
+        self.player.hurt();
+        if self.player.is_dying() {
+            state.jukebox.stop();
+        }
+        self.player.is_dying()
+    }
+
+    fn stomp(&mut self, i: usize, state: &SharedState) {
+        self.emit_stomp_puff(i, state);
+        let reward = Reward::combo(self.player.combo);
+        self.player.combo = self.player.combo.saturating_add(1);
+        self.award(reward, i, state);
+        self.level.entities[i].stomped();
+        self.player.body.accelerate(vec2d!(
+            0.0,
+            ENEMY_KILL_BOUNCE - self.player.body.speed_y()
+        ));
+    }
+
+    // Every enemy a sliding shell runs into counts towards its combo.
+    fn shell_hits(&mut self, shell: usize, state: &SharedState) {
+        let hitbox = self.level.entities[shell].body.hitbox;
+        for i in 0..self.level.entities.len() {
+            let entity = &self.level.entities[i];
+            if i == shell
+                || !matches!(entity.kind, EntityType::Enemy(..))
+                || entity.is_dying()
+                || entity.knocked_off
+                || !entity.body.hitbox.collides(&hitbox)
+            {
+                continue;
+            }
+
+            let combo = self.level.entities[shell].combo;
+            self.level.entities[shell].combo = combo.saturating_add(1);
+            self.award(Reward::combo(combo), i, state);
+            self.level.entities[i].knock_off();
+        }
+    }
+
+    // Enemies touching the player with the star power are knocked off.
+    fn star_power_kill(&mut self, i: usize, state: &SharedState) -> bool {
+        let entity = &self.level.entities[i];
+        if !self.player.has_star_power()
+            || !matches!(entity.kind, EntityType::Enemy(..))
+            || !entity.body.hitbox.collides(&self.player.body.hitbox)
+        {
+            return false;
+        }
+
+        let reward = Reward::combo(self.player.star_combo);
+        self.player.star_combo = self.player.star_combo.saturating_add(1);
+        self.award(reward, i, state);
+        self.level.entities[i].knock_off();
+        true
+    }
+
+    // The game goes on once the death clip of the player ends.
+    fn update_dying_player(&mut self, state: &mut SharedState) {
+        let event =
+            self.player.update_animation(&state.controller, &state.resources);
+        if event != Some(AnimationEvent::Finished(Clip::Death)) {
+            return;
+        }
+
+        if self.score.lives <= 1 {
+            self.state = State::GameOver;
+        } else {
+            let prev_score = self.score;
+            self.restart(state);
+            self.score = prev_score;
+            self.score.lives -= 1;
+            self.state = State::LevelLoading(LOADING_SCREEN_TIME);
+        }
+    }
+
     pub fn update(&mut self, state: &mut SharedState) -> ActivityResult {
         if state.controller.was_key_pressed(Key::Escape) {
-            self.state = match self.state {
-                State::Paused => State::Running,
-                State::Running => State::Paused,
-                state => state,
+            match self.state {
+                State::Paused => self.set_paused(false, state),
+                State::Running => self.set_paused(true, state),
+                _ => (),
             }
         }
 
@@ -350,10 +734,15 @@ impl Game {
             State::Paused => {
                 return self.update_menu(state);
             },
+            State::Running if self.player.is_dying() => {
+                self.update_dying_player(state);
+                self.particles.update();
+            },
             State::Running => {
                 self.update_player(state);
                 self.update_blocks(state);
                 self.update_entities(state);
+                self.particles.update();
             },
             State::LevelLoading(0) => {
                 self.state = State::Running;
@@ -388,24 +777,26 @@ impl Game {
                 )
             },
         };
+        let (width, _) = renderer.logical_size();
         let progress = ((time as f64 / LOADING_SCREEN_TIME as f64)
-            * SCREEN_WIDTH as f64) as u32;
-        let progress_bar = rect!(0, 0, SCREEN_WIDTH - progress, 10);
+            * width as f64) as u32;
+        let progress_bar = rect!(0, 0, width - progress, 10);
         renderer.draw(&progress_bar).show(&mut state.resources);
 
         let score_str =
             format!("Lives: {} Coins: {}", self.score.lives, self.score.coins);
         let score_text = centered_text!(&score_str);
+        let center = renderer.center();
 
         renderer
             .draw(&level_text)
             .scale(0.5)
-            .position(((SCREEN_WIDTH / 2) as i32, (SCREEN_HEIGHT / 2) as i32))
+            .position(center)
             .show(&mut state.resources);
         renderer
             .draw(&score_text)
             .scale(0.25)
-            .position(((SCREEN_WIDTH / 2) as i32, (SCREEN_HEIGHT / 2) as i32))
+            .position(center)
             .shift((0, 100))
             .show(&mut state.resources);
     }
@@ -423,10 +814,21 @@ impl Game {
             .scale(0.25)
             .show(&mut state.resources);
 
+        let (width, _) = renderer.logical_size();
         let coins_text = text_right!(&coins_str);
         renderer
             .draw(&coins_text)
-            .position((SCREEN_WIDTH as i32 - MARGIN, MARGIN))
+            .position((width as i32 - MARGIN, MARGIN))
+            .scale(0.25)
+            .show(&mut state.resources);
+
+        let points_str = format!("SCORE: {}", self.score.points);
+        let points_text = TextBuilder::new(&points_str)
+            .alignment(TextAlignment::Center)
+            .build();
+        renderer
+            .draw(&points_text)
+            .position((width as i32 / 2, MARGIN))
             .scale(0.25)
             .show(&mut state.resources);
     }
@@ -445,6 +847,11 @@ impl Game {
             .camera(self.camera)
             .show(&mut state.resources);
 
+        renderer
+            .draw(&self.particles)
+            .camera(self.camera)
+            .show(&mut state.resources);
+
         match self.state {
             State::Paused => {
                 renderer.fill(Color::RGBA(0, 0, 0, 128));
@@ -458,24 +865,20 @@ impl Game {
                 renderer.clear(Color::RGB(0, 0, 0));
                 let text =
                     centered_text!("CONGRATULATIONS, YOU FINISHED THE GAME!");
+                let center = renderer.center();
                 renderer
                     .draw(&text)
-                    .position((
-                        SCREEN_WIDTH as i32 / 2,
-                        SCREEN_HEIGHT as i32 / 2,
-                    ))
+                    .position(center)
                     .scale(0.2)
                     .show(&mut state.resources);
             },
             State::GameOver => {
                 renderer.clear(Color::RGB(0, 0, 0));
                 let text = centered_text!("GAME OVER");
+                let center = renderer.center();
                 renderer
                     .draw(&text)
-                    .position((
-                        SCREEN_WIDTH as i32 / 2,
-                        SCREEN_HEIGHT as i32 / 2,
-                    ))
+                    .position(center)
                     .show(&mut state.resources);
             },
         }
@@ -484,20 +887,66 @@ impl Game {
 
 impl Score {
     pub fn new() -> Score {
-        Score { lives: 3, coins: 0 }
+        Score {
+            lives:  3,
+            coins:  0,
+            points: 0,
+        }
+    }
+
+    pub fn add(&mut self, reward: Reward) {
+        match reward {
+            Reward::Points(points) => self.points += points,
+            Reward::ExtraLife => self.lives = self.lives.saturating_add(1),
+        }
+    }
+}
+
+impl Reward {
+    // Reward for the kill after `combo` previous kills in the same combo.
+    pub fn combo(combo: u8) -> Reward {
+        match COMBO_POINTS.get(usize::from(combo)) {
+            Some(&points) => Reward::Points(points),
+            None => Reward::ExtraLife,
+        }
+    }
+}
+
+impl fmt::Display for Reward {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reward::Points(points) => write!(f, "{}", points),
+            Reward::ExtraLife => write!(f, "1UP"),
+        }
     }
 }
 
 impl LevelInfo {
-    pub fn new(res: &ResourceManager) -> LevelInfo {
-        let list = res.load_level_list();
+    // Starting from a level outside of the level pack plays just that level.
+    pub fn new(
+        res: &ResourceManager,
+        start: Option<&str>,
+    ) -> ResourceResult<LevelInfo> {
+        let list = res.load_level_list()?;
         if list.is_empty() {
-            panic_with_messagebox!(
-                "Error: no levels specified in the levels.json file!"
-            );
+            return Err(ResourceError::NoLevels(res.level_pack().into()));
         }
 
-        LevelInfo { list, current: 0 }
+        let info = match start {
+            None => LevelInfo { list, current: 0 },
+            Some(name) => {
+                match list.iter().position(|level| level == name) {
+                    Some(current) => LevelInfo { list, current },
+                    None => {
+                        LevelInfo {
+                            list:    vec![String::from(name)],
+                            current: 0,
+                        }
+                    },
+                }
+            },
+        };
+        Ok(info)
     }
 
     pub fn next_level(
@@ -505,15 +954,39 @@ impl LevelInfo {
         res: &ResourceManager,
     ) -> Option<PlayableLevel> {
         self.current += 1;
-        if self.current >= self.list.len() {
-            None
-        } else {
-            Some(self.load_level(res))
-        }
+        self.load_playable(res).ok()
     }
 
-    pub fn load_level(&self, res: &ResourceManager) -> PlayableLevel {
-        let prototype = res.load_existing_level(&self.list[self.current]);
-        PlayableLevel::from(prototype)
+    pub fn current_name(&self) -> &str {
+        &self.list[self.current]
+    }
+
+    pub fn load_level(
+        &self,
+        res: &ResourceManager,
+    ) -> ResourceResult<PlayableLevel> {
+        let prototype = res.load_level(&self.list[self.current])?;
+        Ok(PlayableLevel::from(prototype))
+    }
+
+    // Levels that fail to load are skipped, the game goes on with the next
+    // one in the level pack.
+    fn load_playable(
+        &mut self,
+        res: &ResourceManager,
+    ) -> ResourceResult<PlayableLevel> {
+        let mut error = ResourceError::NoLevels(res.level_pack().into());
+        while self.current < self.list.len() {
+            match self.load_level(res) {
+                Ok(level) => return Ok(level),
+                Err(err) => {
+                    let name = &self.list[self.current];
+                    eprintln!("Skipping level '{}': {}", name, err);
+                    error = err;
+                    self.current += 1;
+                },
+            }
+        }
+        Err(error)
     }
 }
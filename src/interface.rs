@@ -96,11 +96,11 @@ impl<'a, T> ButtonColumnBuilder<'a, T> {
 
     pub fn build(self) -> ButtonColumn<T> {
         let num_of_buttons = self.buttons.len() as u32;
-        let free_height = SCREEN_HEIGHT
+        let free_height = LOGICAL_HEIGHT
             - self.height * num_of_buttons
             - self.separation * (num_of_buttons - 1);
         let y_offset = free_height as i32 / 2;
-        let x = (SCREEN_WIDTH - self.width) as i32 / 2 + self.shift.0;
+        let x = (LOGICAL_WIDTH - self.width) as i32 / 2 + self.shift.0;
 
         let mut buttons: Vec<Button<T>> = vec![];
         for (i, (text, effect)) in self.buttons.into_iter().enumerate() {
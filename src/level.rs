@@ -2,55 +2,78 @@ use crate::background::*;
 use crate::block::*;
 use crate::enemy::*;
 use crate::entity::*;
+use crate::error::*;
 use crate::hitbox::*;
+use crate::parallax::*;
 use crate::render::*;
 use crate::resource::*;
 use crate::texture_id::*;
 
-use rand::Rng;
-
 use sdl2::pixels::Color;
+use sdl2::rect::Point;
 
 use serde::{Deserialize, Serialize};
 
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 pub const LEVEL_HEIGHT: usize = 20;
 pub const LEVEL_WIDTH: usize = 220;
 
 pub type BlockArray<T> = Box<[[T; LEVEL_WIDTH]; LEVEL_HEIGHT]>;
 
+// Number of columns rendered into a single cached background texture.
+const CHUNK_WIDTH: usize = 16;
+
+// The biggest background elements (castles) span this many blocks, so
+// they have to be drawn even if their origin is slightly off the screen.
+const CULLING_MARGIN: usize = 6;
+
+static NEXT_LEVEL_ID: AtomicUsize = AtomicUsize::new(0);
+
+const WATER_COLOR: Color = Color {
+    r: 40,
+    g: 110,
+    b: 255,
+    a: 90,
+};
+
+// Where the player starts in levels made before spawn points existed.
+pub const DEFAULT_SPAWN: (i32, i32) =
+    (10, LEVEL_HEIGHT as i32 * BLOCK_SIZE as i32 - 256);
+
 #[derive(Clone)]
 pub struct Level {
-    pub theme:  LevelTheme,
-    blocks:     BlockArray<Block>,
-    background: BlockArray<BackgroundElement>,
-    entities:   Vec<EntityPrototype>,
+    pub theme:       LevelTheme,
+    // The level is under water from this row down, water background
+    // elements are under water anywhere.
+    pub water_level: Option<usize>,
+    blocks:          BlockArray<Block>,
+    background:      BlockArray<BackgroundElement>,
+    entities:        Vec<EntityPrototype>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct LevelJSON {
-    theme:      LevelTheme,
-    blocks:     Vec<Block>,
-    background: Vec<BackgroundElement>,
-    entities:   Vec<EntityPrototype>,
+    theme:       LevelTheme,
+    #[serde(default)]
+    water_level: Option<usize>,
+    blocks:      Vec<Block>,
+    background:  Vec<BackgroundElement>,
+    entities:    Vec<EntityPrototype>,
 }
 
 #[derive(Clone)]
 pub struct PlayableLevel {
+    id:            usize,
     pub prototype: Level,
     pub blocks:    BlockArray<RealBlock>,
     pub entities:  Vec<Entity>,
-    pub clouds:    Vec<Cloud>,
-}
-
-#[derive(Clone)]
-#[derive(Debug)]
-pub struct Cloud {
-    pub position: (i32, i32),
-    pub is_small: bool,
 }
 
 #[derive(Deserialize, Serialize)]
 #[derive(Copy, Clone)]
+#[derive(PartialEq, Eq, Hash)]
 #[derive(Debug)]
 #[repr(u8)]
 pub enum LevelTheme {
@@ -74,6 +97,7 @@ impl From<&Level> for LevelJSON {
 
         LevelJSON {
             theme: lvl.theme,
+            water_level: lvl.water_level,
             blocks,
             background,
             entities: lvl.entities.clone(),
@@ -100,6 +124,15 @@ impl LevelTheme {
 }
 
 impl PlayableLevel {
+    pub fn is_water(&self, point: Point) -> bool {
+        if point.x() < 0 || point.y() < 0 {
+            return false;
+        }
+        let x = point.x() as usize / BLOCK_SIZE as usize;
+        let y = point.y() as usize / BLOCK_SIZE as usize;
+        x < LEVEL_WIDTH && y < LEVEL_HEIGHT && self.prototype.is_water((x, y))
+    }
+
     pub fn block_hitbox(&self, x: usize, y: usize) -> Option<Hitbox> {
         let block = self.blocks[y][x];
         if block.block.is_collidable() {
@@ -116,12 +149,18 @@ impl Default for Level {
     }
 }
 
-impl From<LevelJSON> for Level {
-    fn from(json: LevelJSON) -> Level {
-        if json.blocks.len() != LEVEL_HEIGHT * LEVEL_WIDTH {
-            panic_with_messagebox!(
-                "Corrupted level state (invalid level size)!"
-            );
+impl TryFrom<LevelJSON> for Level {
+    type Error = ResourceError;
+
+    fn try_from(json: LevelJSON) -> Result<Level, ResourceError> {
+        const SIZE: usize = LEVEL_HEIGHT * LEVEL_WIDTH;
+        for found in [json.blocks.len(), json.background.len()] {
+            if found != SIZE {
+                return Err(ResourceError::LevelSize {
+                    expected: SIZE,
+                    found,
+                });
+            }
         }
 
         let mut blocks = Level::default_blocks();
@@ -139,44 +178,27 @@ impl From<LevelJSON> for Level {
             background[row][col] = bg_elem;
         }
 
-        Level {
+        let mut entities = json.entities;
+        let has_spawn = entities
+            .iter()
+            .any(|entity| matches!(entity.kind, EntityType::PlayerSpawn));
+        if !has_spawn {
+            let spawn = EntityType::PlayerSpawn;
+            entities.push(EntityPrototype::new(spawn, DEFAULT_SPAWN));
+        }
+
+        Ok(Level {
             theme: json.theme,
+            water_level: json.water_level.filter(|&row| row < LEVEL_HEIGHT),
             blocks,
             background,
-            entities: json.entities,
-        }
+            entities,
+        })
     }
 }
 
 impl From<Level> for PlayableLevel {
     fn from(lvl: Level) -> PlayableLevel {
-        fn generate_clouds() -> Vec<Cloud> {
-            const STEP: i32 = 200;
-            let mut x: i32 = 0;
-            let mut clouds: Vec<Cloud> = vec![];
-            let mut rng = rand::thread_rng();
-
-            while x < LEVEL_WIDTH as i32 * BLOCK_SIZE as i32 {
-                let step = rng.gen_range(0, 256);
-                x += STEP + step;
-
-                let max_y = LEVEL_HEIGHT as i32 * BLOCK_SIZE as i32 * 2 / 3;
-                let y = rng.gen_range(0, max_y);
-                let is_small: bool = rand::random();
-
-                if !is_small {
-                    x += STEP;
-                }
-
-                let cloud = Cloud {
-                    position: (x, y),
-                    is_small,
-                };
-
-                clouds.push(cloud);
-            }
-            clouds
-        }
         let mut blocks: BlockArray<RealBlock> = Level::default_blocks();
 
         for (y, row) in lvl.blocks.iter().enumerate() {
@@ -187,13 +209,11 @@ impl From<Level> for PlayableLevel {
 
         let entities = lvl.entities.iter().copied().map(Entity::from).collect();
 
-        let clouds = generate_clouds();
-
         PlayableLevel {
+            id: NEXT_LEVEL_ID.fetch_add(1, Ordering::Relaxed),
             blocks,
             prototype: lvl,
             entities,
-            clouds,
         }
     }
 }
@@ -238,17 +258,40 @@ impl Level {
         );
 
         let flag_pole = EntityPrototype::new(EntityType::EndFlag, FLAG_POS);
-        let entities = vec![flag_pole];
+        let spawn =
+            EntityPrototype::new(EntityType::PlayerSpawn, DEFAULT_SPAWN);
+        let entities = vec![flag_pole, spawn];
 
         Level {
             blocks,
             theme: DEFAULT_THEME,
+            water_level: None,
             background,
             entities,
         }
     }
 
-    pub fn get_bg(&mut self, (x, y): (usize, usize)) -> BackgroundElement {
+    pub fn is_water(&self, (x, y): (usize, usize)) -> bool {
+        self.water_level.is_some_and(|row| y >= row)
+            || self.background[y][x] == BackgroundElement::Water
+    }
+
+    // The water rises up to fully submerge the level.
+    pub fn raise_water(&mut self) {
+        self.water_level = match self.water_level {
+            None => Some(LEVEL_HEIGHT - 1),
+            Some(row) => Some(row.saturating_sub(1)),
+        };
+    }
+
+    pub fn lower_water(&mut self) {
+        self.water_level = match self.water_level {
+            Some(row) if row + 1 < LEVEL_HEIGHT => Some(row + 1),
+            _ => None,
+        };
+    }
+
+    pub fn get_bg(&self, (x, y): (usize, usize)) -> BackgroundElement {
         self.background[y][x]
     }
 
@@ -256,7 +299,7 @@ impl Level {
         self.background[y][x] = bg;
     }
 
-    pub fn get_block(&mut self, (x, y): (usize, usize)) -> Block {
+    pub fn get_block(&self, (x, y): (usize, usize)) -> Block {
         self.blocks[y][x]
     }
 
@@ -272,25 +315,45 @@ impl Level {
         self.blocks[y][x].delete_item();
     }
 
+    pub fn entities(&self) -> &[EntityPrototype] {
+        &self.entities
+    }
+
     pub fn insert_entity(&mut self, entity: EntityPrototype) {
         self.entities.push(entity);
     }
 
+    pub fn retain_entities<F>(&mut self, keep: F)
+    where
+        F: FnMut(&EntityPrototype) -> bool,
+    {
+        self.entities.retain(keep);
+    }
+
     pub fn remove_entity(&mut self, pos: (i32, i32)) {
         self.entities.retain(|entity| entity.position != pos);
     }
 
-    pub fn get_entity(&mut self, pos: (i32, i32)) -> EnemyType {
+    pub fn spawn(&self) -> (i32, i32) {
         self.entities
             .iter()
-            .find(|entity| entity.position == pos)
-            .and_then(|entity| {
-                match entity.kind {
-                    EntityType::Enemy(enemy) => Some(enemy),
-                    _ => None,
-                }
-            })
-            .unwrap_or(EnemyType::Goomba)
+            .find(|entity| matches!(entity.kind, EntityType::PlayerSpawn))
+            .map_or(DEFAULT_SPAWN, |spawn| spawn.position)
+    }
+
+    // Index of the topmost entity under the given point.
+    pub fn entity_at(&self, pos: (i32, i32)) -> Option<usize> {
+        self.entities
+            .iter()
+            .rposition(|entity| entity.editor_hitbox().collides(&pos))
+    }
+
+    pub fn entity_mut(&mut self, index: usize) -> &mut EntityPrototype {
+        &mut self.entities[index]
+    }
+
+    pub fn remove_entity_at(&mut self, index: usize) {
+        self.entities.remove(index);
     }
 }
 
@@ -304,30 +367,265 @@ impl From<LevelTheme> for Color {
     }
 }
 
-impl Drawable for Level {
-    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
-        if data.mode != DrawMode::Game {
-            let color = Color::from(data.object.theme);
-            data.renderer.canvas.set_draw_color(color);
-            data.renderer.canvas.clear();
-        }
+impl Level {
+    // Everything below the water line is tinted blue.
+    fn draw_water(&self, camera: Camera, renderer: &mut Renderer) {
+        let row = match self.water_level {
+            Some(row) => row,
+            None => return,
+        };
 
-        for (y, row) in data.object.background.iter().enumerate() {
-            for (x, &bg) in row.iter().enumerate() {
-                let x = x as i32 * BLOCK_SIZE as i32;
-                let y = y as i32 * BLOCK_SIZE as i32;
+        let top = (row as u32 * BLOCK_SIZE) as i32;
+        let width = LEVEL_WIDTH as u32 * BLOCK_SIZE;
+        let height = (LEVEL_HEIGHT - row) as u32 * BLOCK_SIZE;
+        let (x, y) = camera.translate_coords((0, top));
+        renderer.canvas.set_draw_color(WATER_COLOR);
+        renderer
+            .canvas
+            .fill_rect(rect!(x, y, width, height))
+            .expect("Failed to draw the water!");
+    }
+
+    // Draws the background layer, skipping the elements that are already
+    // drawn from the cached static layer if `only_animated` is set.
+    fn draw_background(
+        &self,
+        call: PartialDrawCall,
+        renderer: &mut Renderer,
+        res: &mut ResourceManager,
+        only_animated: bool,
+    ) {
+        let (cols, rows) = call.camera.visible_blocks(CULLING_MARGIN);
+
+        for y in rows {
+            for x in cols.clone() {
+                let bg = self.background[y][x];
+                if !bg.is_visible()
+                    || only_animated && !res.bg_texture_info(bg).is_animated()
+                {
+                    continue;
+                }
+
+                let pos = (
+                    x as i32 * BLOCK_SIZE as i32,
+                    y as i32 * BLOCK_SIZE as i32,
+                );
                 let themed_bg = ThemedBackgroundElement {
                     element: bg,
-                    theme:   data.object.theme,
+                    theme:   self.theme,
                 };
 
-                pass_draw!(data, &themed_bg).position((x, y)).show(res);
+                call
+                    .position(pos)
+                    .draw_with(&themed_bg, renderer)
+                    .show(res);
+            }
+        }
+    }
+}
+
+impl Level {
+    // Renders the blocks and the background of the whole level into a
+    // thumbnail of the level file, with the textures of the game. Returns
+    // false if the renderer can't render to textures.
+    pub fn cache_thumbnail(
+        &self,
+        file: &LevelFile,
+        scale: f64,
+        renderer: &mut Renderer,
+        res: &mut ResourceManager,
+    ) -> bool {
+        let scaled = |pixels: i32| (f64::from(pixels) * scale) as i32;
+        let width = scaled(LEVEL_WIDTH as i32 * BLOCK_SIZE as i32).max(1);
+        let height = scaled(LEVEL_HEIGHT as i32 * BLOCK_SIZE as i32).max(1);
+        let mut texture =
+            match res.create_layer_texture(width as u32, height as u32) {
+                Ok(texture) => texture,
+                Err(_) => return false,
+            };
+
+        let canvas = match &mut renderer.canvas {
+            Canvas::Window(canvas) => canvas,
+            Canvas::Surface(..) => return false,
+        };
+
+        let res_view: &ResourceManager = res;
+        let theme = self.theme;
+        let draw = |canvas: &mut WindowCanvas| {
+            canvas.set_draw_color(Color::from(theme));
+            canvas.clear();
+
+            let mut copy = |info: &TextureInfo, (x, y): (usize, usize)| {
+                let sprite_y = (info.variant_index(theme) * info.height) as i32;
+                let src_region = info
+                    .atlas_region(rect!(0, sprite_y, info.width, info.height));
+                let x = x as i32 * BLOCK_SIZE as i32;
+                let y = y as i32 * BLOCK_SIZE as i32;
+                let dest = rect!(
+                    scaled(x),
+                    scaled(y),
+                    (scaled(x + info.width as i32) - scaled(x)).max(1),
+                    (scaled(y + info.height as i32) - scaled(y)).max(1)
+                );
+                canvas
+                    .copy(res_view.atlas(), src_region, dest)
+                    .expect("Failed to draw a level thumbnail!");
+            };
+
+            for (y, row) in self.background.iter().enumerate() {
+                for (x, bg) in row.iter().enumerate() {
+                    if bg.is_visible() {
+                        copy(res_view.bg_texture_info(*bg), (x, y));
+                    }
+                }
+            }
+            for (y, row) in self.blocks.iter().enumerate() {
+                for (x, block) in row.iter().enumerate() {
+                    if block.is_visible() && !block.is_hidden() {
+                        copy(res_view.block_texture_info(block.kind()), (x, y));
+                    }
+                }
             }
+        };
+
+        if canvas.with_texture_canvas(&mut texture, draw).is_err() {
+            return false;
         }
 
+        res.insert_thumbnail(file, texture);
+        true
+    }
+}
+
+impl PlayableLevel {
+    // Renders the static part of the background of a chunk of columns into
+    // a texture. Returns false if the renderer can't render to textures.
+    fn cache_background_chunk(
+        &self,
+        chunk: usize,
+        renderer: &mut Renderer,
+        res: &mut ResourceManager,
+    ) -> bool {
+        if res.layer_chunk(self.id, chunk).is_some() {
+            return true;
+        }
+
+        let width = CHUNK_WIDTH as u32 * BLOCK_SIZE;
+        let height = LEVEL_HEIGHT as u32 * BLOCK_SIZE;
+        let mut texture = match res.create_layer_texture(width, height) {
+            Ok(texture) => texture,
+            Err(_) => return false,
+        };
+
+        let level = &self.prototype;
+        let first_col = chunk * CHUNK_WIDTH;
+        // elements bigger than a block may reach in from the previous chunk
+        let cols = first_col.saturating_sub(CULLING_MARGIN)
+            ..(first_col + CHUNK_WIDTH).min(LEVEL_WIDTH);
+
+        // only the window caches layers, offscreen renderers draw each
+        // frame just once
+        let canvas = match &mut renderer.canvas {
+            Canvas::Window(canvas) => canvas,
+            Canvas::Surface(..) => return false,
+        };
+
+        let res_view: &ResourceManager = res;
+        let draw = |canvas: &mut WindowCanvas| {
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, 0));
+            canvas.clear();
+
+            for (y, row) in level.background.iter().enumerate() {
+                for x in cols.clone() {
+                    let bg = row[x];
+                    if !bg.is_visible() {
+                        continue;
+                    }
+
+                    let info = res_view.bg_texture_info(bg);
+                    if info.is_animated() {
+                        continue;
+                    }
+
+                    let sprite_y =
+                        (info.variant_index(level.theme) * info.height) as i32;
+                    let src_region = info.atlas_region(rect!(
+                        0,
+                        sprite_y,
+                        info.width,
+                        info.height
+                    ));
+                    let dest = rect!(
+                        (x as i32 - first_col as i32) * BLOCK_SIZE as i32,
+                        y as i32 * BLOCK_SIZE as i32,
+                        info.width,
+                        info.height
+                    );
+
+                    canvas
+                        .copy(res_view.atlas(), src_region, dest)
+                        .expect("Failed to draw a cached background element!");
+                }
+            }
+        };
+
+        if canvas.with_texture_canvas(&mut texture, draw).is_err() {
+            return false;
+        }
+
+        res.insert_layer_chunk(self.id, chunk, texture);
+        true
+    }
+
+    fn draw_cached_background(
+        &self,
+        camera: Camera,
+        renderer: &mut Renderer,
+        res: &mut ResourceManager,
+    ) -> bool {
+        let (cols, _) = camera.visible_blocks(0);
+        if cols.start >= cols.end {
+            return true;
+        }
+
+        let chunks = cols.start / CHUNK_WIDTH..=(cols.end - 1) / CHUNK_WIDTH;
+        for chunk in chunks {
+            if !self.cache_background_chunk(chunk, renderer, res) {
+                return false;
+            }
+
+            let texture = res.layer_chunk(self.id, chunk).unwrap();
+            let x = (chunk * CHUNK_WIDTH) as i32 * BLOCK_SIZE as i32;
+            let (cam_x, cam_y) = camera.translate_coords((x, 0));
+            let dest = rect!(
+                cam_x,
+                cam_y,
+                CHUNK_WIDTH as u32 * BLOCK_SIZE,
+                LEVEL_HEIGHT as u32 * BLOCK_SIZE
+            );
+
+            renderer
+                .canvas
+                .copy(texture, None, dest)
+                .expect("Failed to draw a cached background chunk!");
+        }
+
+        true
+    }
+}
+
+impl Drawable for Level {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        let call = PartialDrawCall::from(&data);
+        data.object.draw_background(call, data.renderer, res, false);
+        data.object.draw_water(data.camera, data.renderer);
+
+        let (cols, rows) = data.camera.visible_blocks(CULLING_MARGIN);
+
         if data.mode != DrawMode::Game {
-            for (y, row) in data.object.blocks.iter().enumerate() {
-                for (x, &block) in row.iter().enumerate() {
+            for y in rows {
+                for x in cols.clone() {
+                    let block = data.object.blocks[y][x];
                     let x = x as i32 * BLOCK_SIZE as i32;
                     let y = y as i32 * BLOCK_SIZE as i32;
                     let block = ThemedBlock {
@@ -354,17 +652,34 @@ impl Drawable for PlayableLevel {
         data.renderer.canvas.set_draw_color(color);
         data.renderer.canvas.clear();
 
-        for cloud in data.object.clouds.iter() {
-            pass_draw!(data, cloud)
-                .scale(5.0)
-                .camera(data.camera.cloud_camera())
-                .show(res);
+        let parallax = ThemedParallax {
+            theme: data.object.prototype.theme,
+        };
+        pass_draw!(data, &parallax).show(res);
+
+        let cached =
+            data.object
+                .draw_cached_background(data.camera, data.renderer, res);
+        let call = PartialDrawCall::from(&data);
+        data.object
+            .prototype
+            .draw_background(call, data.renderer, res, cached);
+        data.object.prototype.draw_water(data.camera, data.renderer);
+
+        let (behind, in_front): (Vec<&Entity>, Vec<&Entity>) = data
+            .object
+            .entities
+            .iter()
+            .partition(|entity| entity.hides_behind_blocks());
+        for entity in behind {
+            pass_draw!(data, entity).show(res);
         }
 
-        pass_draw!(data, &data.object.prototype).show(res);
+        let (cols, rows) = data.camera.visible_blocks(CULLING_MARGIN);
 
-        for (y, row) in data.object.blocks.iter().enumerate() {
-            for (x, &block) in row.iter().enumerate() {
+        for y in rows {
+            for x in cols.clone() {
+                let block = data.object.blocks[y][x];
                 let bump_amount = match block.state {
                     BlockState::Moving(amount) => amount as i32,
                     _ => 0,
@@ -382,40 +697,8 @@ impl Drawable for PlayableLevel {
             }
         }
 
-        for entity in data.object.entities.iter() {
+        for entity in in_front {
             pass_draw!(data, entity).show(res);
         }
     }
 }
-
-impl Drawable for Cloud {
-    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
-        let texture_id = if data.object.is_small {
-            TextureId::SmallCloud
-        } else {
-            TextureId::BigCloud
-        };
-
-        let info = res.entity_texture_info(texture_id);
-        let (x, y) = data.object.position;
-
-        let width = (info.width as f64 * data.scale) as u32;
-        let height = (info.height as f64 * data.scale) as u32;
-
-        if !data.camera.in_view(rect!(x, y, width, height)) {
-            return;
-        }
-
-        let (cam_x, cam_y) = data.camera.translate_coords((x, y));
-        let dest = rect!(cam_x, cam_y, width, height);
-
-        let path = info.path.clone();
-
-        let src_region = rect!(0, 0, info.width, info.height);
-
-        data.renderer
-            .canvas
-            .copy(&res.texture(&path), src_region, dest)
-            .expect("Failed to draw a cloud! :(");
-    }
-}
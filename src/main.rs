@@ -12,26 +12,47 @@ extern crate num_derive;
 mod utility;
 #[macro_use]
 mod render;
+mod animation;
+mod audio;
+mod atlas;
+mod autotile;
 mod background;
 mod block;
+mod browser;
+mod cli;
 mod controller;
 mod editor;
 mod enemy;
 mod entity;
+mod error;
 mod game;
 mod hitbox;
 mod interface;
 mod level;
 mod menu;
 mod movement;
+mod palette;
+mod parallax;
+mod particle;
+mod paths;
 mod physics;
 mod player;
+mod properties;
 mod resource;
+mod settings;
+mod snapshot;
+mod stamp;
 mod state;
 mod texture_id;
+mod tool;
+mod validation;
+mod watcher;
 
+use cli::*;
+use paths::*;
 use render::*;
 use resource::*;
+use settings::*;
 use state::*;
 use utility::*;
 
@@ -42,34 +63,55 @@ use std::thread::sleep;
 use std::time::{Duration, SystemTime};
 
 fn main() {
-    if let Err(err) = run() {
-        panic_with_messagebox!("{}", err);
+    let options = Options::parse(std::env::args().skip(1))
+        .unwrap_or_else(|err| {
+            eprint!("error: {}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        });
+
+    match &options.command {
+        Command::Launch(launch) => {
+            let pack = &options.pack;
+            let result = GamePaths::find(options.resources.as_deref())
+                .and_then(|paths| run(launch.clone(), pack, paths));
+            if let Err(err) = result {
+                panic_with_messagebox!("{}", err);
+            }
+        },
+        Command::Tool(tool) => std::process::exit(run_tool(tool, &options)),
     }
 }
 
-fn run() -> Result<()> {
+fn run(launch: Launch, pack: &str, paths: GamePaths) -> Result<()> {
     let frame_time: Duration = Duration::from_secs(1) / FPS;
     let context = sdl2::init()?;
     let ttf_context = sdl2::ttf::init()?;
     let video = context.video()?;
+    let settings = Settings::load(&paths.user.join(SETTINGS_FILE));
 
+    let (width, height) = settings.window_size;
     let window = video
-        .window("mario game", SCREEN_WIDTH, SCREEN_HEIGHT)
+        .window("mario game", width, height)
         .position_centered()
+        .resizable()
         .build()
         .map_err(|e| e.to_string())?;
 
     let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
     canvas.set_blend_mode(BlendMode::Blend);
 
-    let mut renderer = Renderer::new(canvas);
+    let mut renderer = Renderer::new(canvas)?;
+    renderer.apply_settings(&settings)?;
     let texture_creator = renderer.canvas.texture_creator();
     let texture_cache = TextureCache::new(&texture_creator);
-    let resources = ResourceManager::new(texture_cache, &ttf_context)?;
+    let mut resources =
+        ResourceManager::new(texture_cache, &ttf_context, paths)?;
+    resources.set_level_pack(pack);
     let video_text_input = video.text_input();
     let text_input = TextInput::new(&video_text_input);
 
-    let mut game_state = GameState::new(resources, &context, text_input)?;
+    let mut game_state =
+        GameState::new(resources, &context, text_input, settings, launch)?;
 
     renderer.clear(Color::RGB(255, 255, 255));
     renderer.canvas.present();
@@ -81,6 +123,8 @@ fn run() -> Result<()> {
             break 'running;
         }
 
+        game_state.update_window(&mut renderer)?;
+
         game_state.draw(&mut renderer);
         let elapsed = now.elapsed()?;
         if let Some(time) = frame_time.checked_sub(elapsed) {
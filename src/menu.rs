@@ -1,7 +1,9 @@
 use crate::controller::*;
+use crate::error::*;
 use crate::interface::*;
 use crate::render::*;
 use crate::resource::*;
+use crate::settings::*;
 use crate::state::*;
 
 use sdl2::pixels::Color;
@@ -12,6 +14,36 @@ pub struct MainMenu {
     buttons: ButtonColumn<MainMenuButtonFunc>,
 }
 
+pub struct SettingsMenu {
+    buttons: ButtonColumn<SettingsEffect>,
+}
+
+// Shown instead of an activity that couldn't be started because of a broken
+// resource, e.g. a level that fails to load.
+pub struct ErrorScreen {
+    lines:   Vec<String>,
+    buttons: ButtonColumn<()>,
+}
+
+// Asks whether to restore the editor changes left in the recovery file.
+pub struct RecoveryMenu {
+    recovery: Option<Recovery>,
+    buttons:  ButtonColumn<RecoveryEffect>,
+}
+
+#[derive(Clone, Copy)]
+enum RecoveryEffect {
+    Restore,
+    Discard,
+}
+
+#[derive(Clone, Copy)]
+enum SettingsEffect {
+    WindowSize,
+    Fullscreen,
+    Back,
+}
+
 impl<'a> OnClick<&mut SharedState<'a>, Option<Activity>>
     for Button<MainMenuButtonFunc>
 {
@@ -28,18 +60,24 @@ impl MainMenu {
         };
 
         let on_start: MainMenuButtonFunc = |state: &mut SharedState| {
-            Some(Activity::new_game(&state.resources))
+            Some(Activity::new_game(&state.resources, None))
+        };
+
+        let on_editor: MainMenuButtonFunc = |state: &mut SharedState| {
+            Some(Activity::new_level_browser(&state.resources))
         };
 
-        let on_editor: MainMenuButtonFunc =
-            |_: &mut SharedState| Some(Activity::FileInputScreen);
+        let on_settings: MainMenuButtonFunc = |state: &mut SharedState| {
+            Some(Activity::new_settings_menu(&state.settings))
+        };
 
-        const BUTTONS_Y_OFFSET: i32 = 150;
+        const BUTTONS_Y_OFFSET: i32 = 100;
 
         let buttons = ButtonColumnBuilder::new()
             .shift_y(BUTTONS_Y_OFFSET)
             .add(("START", on_start))
             .add(("EDITOR", on_editor))
+            .add(("SETTINGS", on_settings))
             .add(("EXIT", on_exit))
             .build();
 
@@ -65,3 +103,190 @@ impl MainMenu {
         renderer.draw(&self.buttons).show(&mut state.resources);
     }
 }
+
+impl SettingsMenu {
+    pub fn new(settings: &Settings) -> SettingsMenu {
+        const BUTTON_WIDTH: u32 = 500;
+
+        let (width, height) = settings.window_size;
+        let window_size = format!("WINDOW: {}x{}", width, height);
+        let fullscreen = if settings.fullscreen {
+            "FULLSCREEN: ON"
+        } else {
+            "FULLSCREEN: OFF"
+        };
+
+        let buttons = ButtonColumnBuilder::new()
+            .width(BUTTON_WIDTH)
+            .add((&window_size, SettingsEffect::WindowSize))
+            .add((fullscreen, SettingsEffect::Fullscreen))
+            .add(("BACK", SettingsEffect::Back))
+            .build();
+
+        SettingsMenu { buttons }
+    }
+
+    pub fn update_and_get_activity(
+        &mut self,
+        state: &mut SharedState,
+    ) -> Option<Activity> {
+        if state.controller.was_key_pressed(Key::Escape) {
+            return Some(Activity::new_main_menu(&state.resources));
+        }
+
+        match self.buttons.effect_if_clicked(&state.controller).copied() {
+            Some(SettingsEffect::WindowSize) => {
+                state.settings.next_window_size();
+                state.settings_changed = true;
+            },
+            Some(SettingsEffect::Fullscreen) => {
+                state.settings.toggle_fullscreen();
+                state.settings_changed = true;
+            },
+            Some(SettingsEffect::Back) => {
+                return Some(Activity::new_main_menu(&state.resources));
+            },
+            None => (),
+        }
+
+        if state.settings_changed {
+            *self = SettingsMenu::new(&state.settings);
+        }
+
+        None
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, state: &mut SharedState) {
+        renderer.canvas.set_draw_color(Color::RGB(88, 100, 255));
+        renderer.canvas.clear();
+        renderer.draw(&self.buttons).show(&mut state.resources);
+    }
+}
+
+impl RecoveryMenu {
+    pub fn new(recovery: Recovery) -> RecoveryMenu {
+        const BUTTONS_Y_OFFSET: i32 = 60;
+
+        let buttons = ButtonColumnBuilder::new()
+            .shift_y(BUTTONS_Y_OFFSET)
+            .add(("RESTORE", RecoveryEffect::Restore))
+            .add(("DISCARD", RecoveryEffect::Discard))
+            .build();
+
+        RecoveryMenu {
+            recovery: Some(recovery),
+            buttons,
+        }
+    }
+
+    pub fn update_and_get_activity(
+        &mut self,
+        state: &mut SharedState,
+    ) -> Option<Activity> {
+        let effect = self.buttons.effect_if_clicked(&state.controller);
+        match effect.copied() {
+            Some(RecoveryEffect::Restore) => {
+                let recovery = self.recovery.take()?;
+                Some(Activity::new_recovered_editor(&state.resources, recovery))
+            },
+            Some(RecoveryEffect::Discard) => {
+                state.resources.discard_recovery();
+                Some(Activity::new_main_menu(&state.resources))
+            },
+            None => None,
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, state: &mut SharedState) {
+        renderer.canvas.set_draw_color(Color::RGB(88, 100, 255));
+        renderer.canvas.clear();
+
+        let name = self
+            .recovery
+            .as_ref()
+            .map_or("", |recovery| &recovery.level_name);
+        let message = format!("Unsaved changes of level '{}' were found", name);
+        let center = renderer.center();
+
+        renderer
+            .draw(&centered_text!(&message))
+            .position(center)
+            .shift((0, -120))
+            .scale(0.2)
+            .show(&mut state.resources);
+
+        renderer.draw(&self.buttons).show(&mut state.resources);
+    }
+}
+
+impl ErrorScreen {
+    pub fn new(error: &ResourceError) -> ErrorScreen {
+        const BUTTONS_Y_OFFSET: i32 = 200;
+        const LINE_LENGTH: usize = 60;
+
+        let buttons = ButtonColumnBuilder::new()
+            .shift_y(BUTTONS_Y_OFFSET)
+            .add(("BACK", ()))
+            .build();
+
+        ErrorScreen {
+            lines: wrap_words(&error.to_string(), LINE_LENGTH),
+            buttons,
+        }
+    }
+
+    pub fn update_and_get_activity(
+        &self,
+        state: &mut SharedState,
+    ) -> Option<Activity> {
+        let back = state.controller.was_key_pressed(Key::Escape)
+            || self.buttons.effect_if_clicked(&state.controller).is_some();
+        if back {
+            Some(Activity::new_main_menu(&state.resources))
+        } else {
+            None
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer, state: &mut SharedState) {
+        const LINE_HEIGHT: i32 = 36;
+
+        renderer.canvas.set_draw_color(Color::RGB(88, 100, 255));
+        renderer.canvas.clear();
+        let center = renderer.center();
+
+        renderer
+            .draw(&centered_text!("Error!"))
+            .position(center)
+            .shift((0, -240))
+            .scale(0.3)
+            .show(&mut state.resources);
+
+        for (i, line) in self.lines.iter().enumerate() {
+            renderer
+                .draw(&centered_text!(line))
+                .position(center)
+                .shift((0, -160 + i as i32 * LINE_HEIGHT))
+                .scale(0.2)
+                .show(&mut state.resources);
+        }
+
+        renderer.draw(&self.buttons).show(&mut state.resources);
+    }
+}
+
+// Splits the text into lines of at most the given length, words longer
+// than a line (like file paths) get a line of their own.
+fn wrap_words(text: &str, max_length: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + word.len() <= max_length => {
+                line.push(' ');
+                line.push_str(word);
+            },
+            _ => lines.push(String::from(word)),
+        }
+    }
+    lines
+}
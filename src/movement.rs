@@ -129,14 +129,22 @@ impl PhysicalBody {
 
         for y in range.from_y..=range.to_y {
             for x in range.from_x..=range.to_x {
-                if !world.blocks[y][x].block.is_bumpable() {
+                let block = world.blocks[y][x].block;
+                if !block.is_bumpable() {
                     continue;
                 }
 
-                if let Some(block_hitbox) = world.block_hitbox(x, y) {
-                    if block_hitbox.contains_point(head) {
-                        world.blocks[y][x].state = BlockState::Bumped;
-                    }
+                // Hidden blocks are only hit by jumping into them from below.
+                let block_hitbox = Block::hitbox(x, y);
+                let from_below = movement.y < 0
+                    && self.hitbox.top() >= block_hitbox.bottom();
+                if block.is_hidden() && !from_below {
+                    continue;
+                }
+
+                if block_hitbox.contains_point(head) {
+                    world.blocks[y][x].block.reveal();
+                    world.blocks[y][x].state = BlockState::Bumped;
                 }
             }
         }
@@ -197,6 +205,8 @@ impl PhysicalBody {
             self.grounded = false;
         }
 
+        self.submerged = world.is_water(self.hitbox.center());
+
         if acceptable_speed.x >= 1.0 {
             self.direction = XDirection::Right;
         } else if acceptable_speed.x <= -1.0 {
@@ -0,0 +1,294 @@
+use crate::autotile::*;
+use crate::background::*;
+use crate::block::*;
+use crate::controller::*;
+use crate::editor::*;
+use crate::enemy::*;
+use crate::entity::*;
+use crate::level::*;
+use crate::render::*;
+use crate::resource::*;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+pub const PALETTE_WIDTH: u32 = 280;
+
+const CELL_SIZE: u32 = 40;
+const CELL_GAP: u32 = 4;
+const COLUMNS: usize = 6;
+const HEADING_HEIGHT: u32 = 24;
+const MARGIN: u32 = 8;
+const MAX_RECENT: usize = COLUMNS;
+
+// Side panel of the editor showing every element that can be placed,
+// grouped by layer.
+pub struct Palette {
+    visible: bool,
+    groups:  Vec<(&'static str, Vec<Selection>)>,
+    recent:  Vec<Selection>,
+    // Pixels the content is scrolled up by, when it's taller than the
+    // screen.
+    scroll:  i32,
+}
+
+pub struct ThemedPalette<'a> {
+    pub palette:  &'a Palette,
+    pub selected: Selection,
+    pub theme:    LevelTheme,
+}
+
+#[derive(Clone, Copy)]
+enum PaletteItem {
+    Heading(&'static str),
+    Cell(Selection),
+}
+
+impl Palette {
+    pub fn new() -> Palette {
+        let blocks = cycle(Selection::Block(Block::default_visible()));
+        let terrain = cycle(Selection::Terrain(Terrain::Ground));
+        let background = cycle(Selection::Background(
+            BackgroundElement::default_visible(),
+        ));
+        let collectibles = vec![
+            Selection::Collectible(Collectible::Coins(1)),
+            Selection::Collectible(Collectible::MultiCoin),
+            Selection::Collectible(Collectible::Mushroom),
+            Selection::Collectible(Collectible::OneUp),
+            Selection::Collectible(Collectible::Star),
+            Selection::Collectible(Collectible::Vine),
+        ];
+        let mut entities = cycle(Selection::Enemy(EnemyType::Goomba));
+        entities.push(Selection::Warp);
+
+        Palette {
+            visible: false,
+            groups:  vec![
+                ("BLOCKS", blocks),
+                ("TERRAIN", terrain),
+                ("BACKGROUND", background),
+                ("COLLECTIBLES", collectibles),
+                ("ENTITIES", entities),
+            ],
+            recent:  vec![],
+            scroll:  0,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible ^= true;
+    }
+
+    pub fn is_hovered(&self, (x, y): (i32, i32)) -> bool {
+        self.visible && x >= (LOGICAL_WIDTH - PALETTE_WIDTH) as i32
+    }
+
+    // Most recently used elements come first, stamps have their own list.
+    pub fn remember(&mut self, selection: Selection) {
+        if let Selection::Stamp(..) = selection {
+            return;
+        }
+        if self.recent.first() == Some(&selection) {
+            return;
+        }
+
+        self.recent.retain(|&other| other != selection);
+        self.recent.insert(0, selection);
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    // The mouse wheel scrolls the palette by one row of cells.
+    pub fn scroll(&mut self, controller: &Controller) {
+        let step = (CELL_SIZE + CELL_GAP) as i32;
+        let overflow = self.content_height() - LOGICAL_HEIGHT as i32;
+        self.scroll = (self.scroll - controller.mouse().scroll() * step)
+            .min(overflow)
+            .max(0);
+    }
+
+    pub fn pick(&self, controller: &Controller) -> Option<Selection> {
+        if !controller.was_button_pressed(MButton::Left) {
+            return None;
+        }
+
+        let (x, y) = controller.mouse().pos();
+        self.layout()
+            .into_iter()
+            .find(|(_, rect)| rect.contains_point((x, y)))
+            .and_then(|(item, _)| match item {
+                PaletteItem::Cell(selection) => Some(selection),
+                PaletteItem::Heading(..) => None,
+            })
+    }
+
+    fn layout(&self) -> Vec<(PaletteItem, Rect)> {
+        let left = (LOGICAL_WIDTH - PALETTE_WIDTH + MARGIN) as i32;
+        let mut top = MARGIN as i32 - self.scroll;
+        let mut items = vec![];
+
+        let recent = Some(("RECENT", &self.recent))
+            .filter(|(_, recent)| !recent.is_empty());
+        let groups = self.groups.iter().map(|(name, group)| (*name, group));
+
+        for (name, group) in recent.into_iter().chain(groups) {
+            let width = PALETTE_WIDTH - 2 * MARGIN;
+            let heading = rect!(left, top, width, HEADING_HEIGHT);
+            items.push((PaletteItem::Heading(name), heading));
+            top += HEADING_HEIGHT as i32;
+
+            for (i, &selection) in group.iter().enumerate() {
+                let column = (i % COLUMNS) as u32;
+                let row = (i / COLUMNS) as u32;
+                let rect = rect!(
+                    left + (column * (CELL_SIZE + CELL_GAP)) as i32,
+                    top + (row * (CELL_SIZE + CELL_GAP)) as i32,
+                    CELL_SIZE,
+                    CELL_SIZE
+                );
+                items.push((PaletteItem::Cell(selection), rect));
+            }
+
+            let rows = group.len().div_ceil(COLUMNS);
+            top += (rows as u32 * (CELL_SIZE + CELL_GAP) + MARGIN) as i32;
+        }
+
+        items
+    }
+
+    fn content_height(&self) -> i32 {
+        let bottom = self
+            .layout()
+            .iter()
+            .map(|(_, rect)| rect.bottom())
+            .max()
+            .unwrap_or(0);
+        bottom + self.scroll + MARGIN as i32
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new()
+    }
+}
+
+// Every element of a layer, in the order of scrolling through them.
+fn cycle(first: Selection) -> Vec<Selection> {
+    let mut elements = vec![first];
+    let mut next = first.next();
+    while next != first {
+        elements.push(next);
+        next = next.next();
+    }
+    elements
+}
+
+impl Drawable for ThemedPalette<'_> {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        let palette = data.object.palette;
+        if !palette.visible {
+            return;
+        }
+
+        let left = (LOGICAL_WIDTH - PALETTE_WIDTH) as i32;
+        let panel = rect!(left, 0, PALETTE_WIDTH, LOGICAL_HEIGHT);
+        data.renderer.canvas.set_draw_color(Color::RGBA(0, 0, 0, 192));
+        data.renderer.canvas.fill_rect(panel).unwrap();
+
+        data.renderer.canvas.set_clip_rect(panel);
+        for (item, rect) in palette.layout() {
+            match item {
+                PaletteItem::Heading(name) => {
+                    data.renderer
+                        .draw(&text!(name))
+                        .position((rect.x(), rect.y()))
+                        .scale(0.18)
+                        .show(res);
+                },
+                PaletteItem::Cell(selection) => {
+                    let theme = data.object.theme;
+                    draw_thumbnail(data.renderer, selection, theme, rect, res);
+
+                    let color = if selection == data.object.selected {
+                        Color::RGB(255, 255, 0)
+                    } else {
+                        Color::RGB(80, 80, 80)
+                    };
+                    data.renderer.canvas.set_draw_color(color);
+                    data.renderer.canvas.draw_rect(rect).unwrap();
+                },
+            }
+        }
+        data.renderer.canvas.set_clip_rect(None);
+    }
+}
+
+// Thumbnails keep the aspect ratio of the element and fit into the cell.
+fn draw_thumbnail(
+    renderer: &mut Renderer,
+    selection: Selection,
+    theme: LevelTheme,
+    rect: Rect,
+    res: &mut ResourceManager,
+) {
+    if let Selection::Warp = selection {
+        let scale = f64::from(CELL_SIZE) / f64::from(BLOCK_SIZE);
+        let warp = EntityPrototype::new(EntityType::Warp, (rect.x(), rect.y()));
+        PartialDrawCall::new()
+            .scale(scale)
+            .draw_with(&warp, renderer)
+            .show(res);
+        return;
+    }
+
+    let info = match selection {
+        Selection::Block(block) => res.block_texture_info(block.kind()),
+        Selection::Terrain(terrain) => res.block_texture_info(terrain.icon()),
+        Selection::Background(bg) => res.bg_texture_info(bg),
+        Selection::Collectible(c) => res.entity_texture_info(c.texture_id()),
+        Selection::Enemy(enemy) => res.entity_texture_info(enemy.texture_id()),
+        Selection::Warp | Selection::Stamp(..) => return,
+    };
+
+    let largest = info.width.max(info.height);
+    let scale = f64::from(CELL_SIZE) / f64::from(largest);
+    let width = (f64::from(info.width) * scale) as i32;
+    let height = (f64::from(info.height) * scale) as i32;
+    let (off_x, off_y) = info.hitbox_offset();
+    let pos = (
+        rect.x() + (CELL_SIZE as i32 - width) / 2,
+        rect.y() + (CELL_SIZE as i32 - height) / 2,
+    );
+
+    let call = PartialDrawCall::new().position(pos).scale(scale);
+    match selection {
+        Selection::Block(block) => {
+            let block = ThemedBlock { block, theme };
+            call.draw_with(&block, renderer).show(res);
+        },
+        Selection::Terrain(terrain) => {
+            let block = Block::from(terrain.icon());
+            let block = ThemedBlock { block, theme };
+            call.draw_with(&block, renderer).show(res);
+        },
+        Selection::Background(element) => {
+            let bg = ThemedBackgroundElement { element, theme };
+            call.draw_with(&bg, renderer).show(res);
+        },
+        Selection::Collectible(collectible) => {
+            call.draw_with(&collectible, renderer).show(res);
+        },
+        Selection::Enemy(enemy) => {
+            // Enemies are drawn shifted by the offset of their hitbox.
+            call.position((pos.0 - off_x, pos.1 - off_y))
+                .draw_with(&enemy, renderer)
+                .show(res);
+        },
+        Selection::Warp | Selection::Stamp(..) => (),
+    }
+}
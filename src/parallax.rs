@@ -0,0 +1,93 @@
+use crate::level::*;
+use crate::render::*;
+use crate::resource::*;
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[derive(Clone)]
+#[derive(Debug)]
+pub struct ParallaxLayer {
+    pub path:            String,
+    pub width:           u32,
+    pub height:          u32,
+    #[serde(default = "default_scale")]
+    pub scale:           f64,
+    // 0.0 keeps the layer fixed on the screen, 1.0 moves it together with
+    // the level.
+    #[serde(default = "default_scroll_factor")]
+    pub scroll_factor:   f64,
+    #[serde(default)]
+    pub vertical_offset: i32,
+    #[serde(default)]
+    pub tiling:          bool,
+    // Horizontal gap between repeated images of a tiled layer.
+    #[serde(default)]
+    pub spacing:         u32,
+    // Pixels per frame the layer moves on its own.
+    #[serde(default)]
+    pub auto_scroll:     f64,
+}
+
+pub struct ThemedParallax {
+    pub theme: LevelTheme,
+}
+
+impl Drawable for ThemedParallax {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        let layers = res.parallax_layers(data.object.theme);
+        for layer in layers.iter() {
+            pass_draw!(data, layer).show(res);
+        }
+    }
+}
+
+impl Drawable for ParallaxLayer {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        let layer = data.object;
+        let width = (layer.width as f64 * layer.scale) as i32;
+        let height = (layer.height as f64 * layer.scale) as i32;
+
+        let (cam_x, cam_y) = data.camera.to_real_coords((0, 0));
+        let scroll = cam_x as f64 * layer.scroll_factor
+            + data.tick as f64 * layer.auto_scroll;
+        let y = layer.vertical_offset
+            - (cam_y as f64 * layer.scroll_factor) as i32;
+
+        // A tiled layer without a width would be repeated forever.
+        let period = width + layer.spacing as i32;
+        if layer.tiling && period <= 0 {
+            return;
+        }
+
+        let (screen_width, _) = data.renderer.logical_size();
+        let src_region = rect!(0, 0, layer.width, layer.height);
+        let texture = res.texture(&layer.path);
+
+        let (mut x, period) = if layer.tiling {
+            let first = (scroll / period as f64).floor() as i32;
+            (first * period - scroll as i32, period)
+        } else {
+            (-scroll as i32, i32::MAX)
+        };
+
+        while x < screen_width as i32 {
+            let dest = rect!(x, y, width, height);
+            data.renderer
+                .canvas
+                .copy(&texture, src_region, dest)
+                .expect("Failed to draw a parallax layer!");
+
+            x = x.saturating_add(period);
+        }
+    }
+}
+
+// for serde_json default values purposes
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn default_scroll_factor() -> f64 {
+    1.0
+}
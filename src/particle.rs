@@ -0,0 +1,303 @@
+use crate::block::*;
+use crate::level::*;
+use crate::render::*;
+use crate::resource::*;
+use crate::texture_id::*;
+
+use rand::Rng;
+
+use serde::Deserialize;
+
+use sdl2::pixels::Color;
+
+use vector2d::Vector2D;
+
+// Particles beyond this limit are dropped instead of growing the pool.
+const MAX_PARTICLES: usize = 512;
+
+// Names of the emitters in the `emitters` section of `info.json`.
+#[derive(Deserialize)]
+#[derive(Copy, Clone)]
+#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug)]
+pub enum Emitter {
+    LandingDust,
+    SkidDust,
+    StompPuff,
+    ScorePopup,
+    StarSparkle,
+    Coin,
+    BlockFragments,
+}
+
+#[derive(Deserialize)]
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+pub struct EmitterInfo {
+    #[serde(default = "default_count")]
+    pub count:    u32,
+    // Frames until a particle disappears.
+    pub lifetime: u32,
+    // Initial speeds are picked at random from these ranges.
+    #[serde(default)]
+    pub speed_x:  (f64, f64),
+    #[serde(default)]
+    pub speed_y:  (f64, f64),
+    #[serde(default)]
+    pub gravity:  f64,
+    // Particles start at a random offset of up to this many pixels.
+    #[serde(default)]
+    pub spread:   i32,
+    // Squares and text become transparent towards the end of their life.
+    #[serde(default)]
+    pub fade:     bool,
+    pub look:     ParticleLook,
+}
+
+#[derive(Deserialize)]
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+pub enum ParticleLook {
+    Square { size: u32, color: (u8, u8, u8) },
+    // An entity texture, animated over the life of the particle.
+    Texture(TextureId),
+    // A piece of the block the particles were emitted from.
+    BlockPiece { size: u32 },
+    // The text the particles were emitted with.
+    Text { scale: f64, color: (u8, u8, u8) },
+}
+
+// What a particle was emitted from, for the looks that depend on it.
+#[derive(Clone)]
+#[derive(Debug)]
+pub enum ParticleSource {
+    Plain,
+    Block(BlockType, LevelTheme),
+    Text(String),
+}
+
+struct Particle {
+    position: Vector2D<f64>,
+    speed:    Vector2D<f64>,
+    age:      u32,
+    info:     EmitterInfo,
+    source:   ParticleSource,
+}
+
+// Short-lived effects. Unlike entities they don't collide with anything,
+// they just move and disappear.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+}
+
+impl ParticleSystem {
+    pub fn new() -> ParticleSystem {
+        ParticleSystem {
+            particles: Vec::with_capacity(MAX_PARTICLES),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn emit(
+        &mut self,
+        res: &ResourceManager,
+        emitter: Emitter,
+        position: (i32, i32),
+    ) {
+        self.emit_from(res, emitter, position, ParticleSource::Plain);
+    }
+
+    // Emitters missing in `info.json` don't emit anything.
+    pub fn emit_from(
+        &mut self,
+        res: &ResourceManager,
+        emitter: Emitter,
+        (x, y): (i32, i32),
+        source: ParticleSource,
+    ) {
+        let info = match res.emitter_info(emitter) {
+            Some(info) => *info,
+            None => return,
+        };
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..info.count {
+            if self.particles.len() >= MAX_PARTICLES {
+                return;
+            }
+
+            let offset_x = random_in(&mut rng, (-info.spread, info.spread));
+            let offset_y = random_in(&mut rng, (-info.spread, info.spread));
+            let speed_x = random_in(&mut rng, info.speed_x);
+            let speed_y = random_in(&mut rng, info.speed_y);
+            self.particles.push(Particle {
+                position: vec2d!(
+                    f64::from(x + offset_x),
+                    f64::from(y + offset_y)
+                ),
+                speed: vec2d!(speed_x, speed_y),
+                age: 0,
+                info,
+                source: source.clone(),
+            });
+        }
+    }
+
+    pub fn update(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.position += particle.speed;
+            particle.speed.y += particle.info.gravity;
+            particle.age += 1;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.info.lifetime);
+    }
+}
+
+fn random_in<T, R>(rng: &mut R, (low, high): (T, T)) -> T
+where
+    T: PartialOrd + rand::distributions::uniform::SampleUniform,
+    R: Rng,
+{
+    if low < high {
+        rng.gen_range(low, high)
+    } else {
+        low
+    }
+}
+
+impl Particle {
+    fn alpha(&self) -> u8 {
+        if !self.info.fade {
+            return 255;
+        }
+        let left = self.info.lifetime.saturating_sub(self.age);
+        (255 * left / self.info.lifetime.max(1)) as u8
+    }
+}
+
+impl Drawable for ParticleSystem {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        for particle in data.object.particles.iter() {
+            let position =
+                (particle.position.x as i32, particle.position.y as i32);
+            pass_draw!(data, particle).position(position).show(res);
+        }
+    }
+}
+
+impl Drawable for Particle {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        match data.object.info.look {
+            ParticleLook::Square { size, color } => {
+                show_square(data, size, color);
+            },
+            ParticleLook::Texture(texture) => {
+                show_texture(data, texture, res);
+            },
+            ParticleLook::BlockPiece { size } => {
+                show_block_piece(data, size, res);
+            },
+            ParticleLook::Text { scale, color } => {
+                show_text(data, scale, color, res);
+            },
+        }
+    }
+}
+
+fn show_square(
+    data: DrawCall<Particle>,
+    size: u32,
+    (r, g, b): (u8, u8, u8),
+) {
+    let (x, y) = data.position;
+    if !data.camera.in_view(rect!(x, y, size, size)) {
+        return;
+    }
+
+    let (cam_x, cam_y) = data.camera.translate_coords((x, y));
+    let color = Color::RGBA(r, g, b, data.object.alpha());
+    data.renderer.canvas.set_draw_color(color);
+    data.renderer
+        .canvas
+        .fill_rect(rect!(cam_x, cam_y, size, size))
+        .expect("Failed to draw a particle!");
+}
+
+fn show_texture(
+    data: DrawCall<Particle>,
+    texture: TextureId,
+    res: &mut ResourceManager,
+) {
+    let info = res.entity_texture_info(texture);
+    let (x, y) = data.position;
+    if !data.camera.in_view(rect!(x, y, info.width, info.height)) {
+        return;
+    }
+
+    let sprite_x = info.frame_index(data.object.age) * info.width;
+    let src_region = rect!(sprite_x, 0, info.width, info.height);
+    let (cam_x, cam_y) = data.camera.translate_coords((x, y));
+    let dest = rect!(cam_x, cam_y, info.width, info.height);
+
+    let path = info.path.clone();
+    data.renderer
+        .canvas
+        .copy(&res.texture(&path), src_region, dest)
+        .expect("Failed to draw a particle!");
+}
+
+fn show_block_piece(
+    data: DrawCall<Particle>,
+    size: u32,
+    res: &mut ResourceManager,
+) {
+    let (kind, theme) = match data.object.source {
+        ParticleSource::Block(kind, theme) => (kind, theme),
+        _ => return,
+    };
+
+    let (x, y) = data.position;
+    if !data.camera.in_view(rect!(x, y, size, size)) {
+        return;
+    }
+
+    let info = res.block_texture_info(kind);
+    let sprite_y = (info.variant_index(theme) * info.height) as i32;
+    let src_region = info.atlas_region(rect!(0, sprite_y, size, size));
+
+    let (cam_x, cam_y) = data.camera.translate_coords((x, y));
+    let dest = rect!(cam_x, cam_y, size, size);
+
+    data.renderer
+        .canvas
+        .copy(res.atlas(), src_region, dest)
+        .expect("Failed to draw a particle!");
+}
+
+fn show_text(
+    data: DrawCall<Particle>,
+    scale: f64,
+    (r, g, b): (u8, u8, u8),
+    res: &mut ResourceManager,
+) {
+    let text = match &data.object.source {
+        ParticleSource::Text(text) => text,
+        _ => return,
+    };
+
+    let color = Color::RGBA(r, g, b, data.object.alpha());
+    let text = TextBuilder::new(text)
+        .alignment(TextAlignment::TotalCenter)
+        .color(color)
+        .build();
+    pass_draw!(data, &text).scale(scale).show(res);
+}
+
+// for serde_json default values purposes
+fn default_count() -> u32 {
+    1
+}
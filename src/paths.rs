@@ -0,0 +1,146 @@
+use crate::utility::*;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const RESOURCES_VAR: &str = "MARIO_CLONE_RESOURCES";
+
+const APP_NAME: &str = env!("CARGO_PKG_NAME");
+// Every resource directory has it, so that an unrelated directory named
+// `resources` isn't mistaken for one.
+const MARKER_FILE: &str = "textures/info.json";
+
+// Where the game reads its resources from and where it writes the data of
+// the player, e.g. the settings. The resource directory may be read-only
+// when the game is installed system-wide.
+#[derive(Clone, Debug)]
+pub struct GamePaths {
+    pub resources: PathBuf,
+    pub user:      PathBuf,
+}
+
+impl GamePaths {
+    // The resource directory is the first one of:
+    // - the `--resources` command line option,
+    // - the `MARIO_CLONE_RESOURCES` environment variable,
+    // - `mario-clone/resources` in the XDG data directories,
+    // - `resources` next to the executable or in one of its parents, which
+    //   finds the repository when started through `cargo run`.
+    pub fn find(option: Option<&Path>) -> Result<GamePaths> {
+        let resources = find_resources(option)?;
+        let user = user_data_dir().unwrap_or_else(|| resources.clone());
+        if let Err(err) = fs::create_dir_all(&user) {
+            eprintln!("Failed to create {}: {}", user.display(), err);
+        }
+        Ok(GamePaths { resources, user })
+    }
+
+    // Levels and stamps made in the editor are saved in the user directory,
+    // where they take precedence over the ones shipped with the game.
+    pub fn saved_file(&self, dir: &str, file: &str) -> io::Result<PathBuf> {
+        let dir = self.user.join(dir);
+        fs::create_dir_all(&dir)?;
+        Ok(dir.join(file))
+    }
+
+    pub fn find_file(&self, dir: &str, file: &str) -> PathBuf {
+        let saved = self.user.join(dir).join(file);
+        if saved.exists() {
+            saved
+        } else {
+            self.resources.join(dir).join(file)
+        }
+    }
+
+    // The directories holding files of one kind, the user directory last
+    // so that its files override the shipped ones.
+    pub fn file_dirs(&self, dir: &str) -> Vec<PathBuf> {
+        let mut dirs = vec![self.resources.join(dir)];
+        if self.user != self.resources {
+            dirs.push(self.user.join(dir));
+        }
+        dirs
+    }
+}
+
+fn find_resources(option: Option<&Path>) -> Result<PathBuf> {
+    if let Some(path) = option {
+        return check_resources(path, "--resources");
+    }
+    if let Some(path) = env::var_os(RESOURCES_VAR) {
+        return check_resources(Path::new(&path), RESOURCES_VAR);
+    }
+
+    let mut candidates: Vec<PathBuf> = data_dirs()
+        .into_iter()
+        .map(|dir| dir.join(APP_NAME).join("resources"))
+        .collect();
+    if let Some(exe_dir) = env::current_exe()?.parent() {
+        candidates.extend(exe_dir.ancestors().map(|dir| dir.join("resources")));
+    }
+
+    match candidates.iter().find(|dir| is_resource_dir(dir)) {
+        Some(dir) => Ok(dir.clone()),
+        None => {
+            let searched: Vec<String> = candidates
+                .iter()
+                .map(|dir| format!("  {}", dir.display()))
+                .collect();
+            Err(format!(
+                "Couldn't find the resource directory, set it with \
+                 --resources or {}. Searched in:\n{}",
+                RESOURCES_VAR,
+                searched.join("\n")
+            )
+            .into())
+        },
+    }
+}
+
+fn check_resources(path: &Path, source: &str) -> Result<PathBuf> {
+    if is_resource_dir(path) {
+        Ok(path.to_path_buf())
+    } else {
+        Err(format!(
+            "{} given by {} isn't a resource directory, {} is missing",
+            path.display(),
+            source,
+            MARKER_FILE
+        )
+        .into())
+    }
+}
+
+fn is_resource_dir(path: &Path) -> bool {
+    path.join(MARKER_FILE).is_file()
+}
+
+// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, with the defaults of the
+// XDG base directory specification.
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = data_home().into_iter().collect();
+    let data_dirs = env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share/:/usr/share/".into());
+    dirs.extend(env::split_paths(&data_dirs).filter(|dir| dir.is_absolute()));
+    dirs
+}
+
+fn data_home() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".local/share"))
+        })
+}
+
+// `$XDG_DATA_HOME/mario-clone`, or the roaming application data on Windows.
+fn user_data_dir() -> Option<PathBuf> {
+    data_home()
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .map(|dir| dir.join(APP_NAME))
+}
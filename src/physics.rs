@@ -20,6 +20,8 @@ pub struct PhysicalBody {
     physics:       Physics,
     pub hitbox:    Hitbox,
     pub grounded:  bool,
+    // Set when the center of the body is under water.
+    pub submerged: bool,
     pub direction: XDirection,
 }
 
@@ -29,6 +31,7 @@ impl PhysicalBody {
             physics: Physics::new(mass),
             hitbox,
             grounded: false,
+            submerged: false,
             direction: XDirection::Still,
         }
     }
@@ -60,7 +63,14 @@ impl PhysicalBody {
     }
 
     pub fn accelerate(&mut self, accel: Vector2D<f64>) {
-        self.physics.accelerate(self.grounded, accel);
+        self.physics.accelerate(self.grounded, self.submerged, accel);
+    }
+
+    // Bodies that steer themselves, like climbing players or swimming
+    // enemies, move at the given speed right away, without momentum and
+    // gravity.
+    pub fn move_at(&mut self, speed: Vector2D<f64>) {
+        self.physics.speed = speed;
     }
 
     pub fn move_by_vec(&mut self, vec: Vector2D<i32>) {
@@ -165,6 +175,12 @@ const AIR_DRAG_VEC: Vector2D<f64> = vec2d!(AIR_DRAG, AIR_DRAG);
 const GROUND_DRAG: f64 = 0.10;
 const GROUND_DRAG_VEC: Vector2D<f64> = vec2d!(GROUND_DRAG, 0.0);
 
+// Bodies sink slowly in water and can't move through it as fast as
+// through the air.
+const WATER_GRAVITY: f64 = 0.2;
+const WATER_DRAG: f64 = 0.09;
+const WATER_DRAG_VEC: Vector2D<f64> = vec2d!(WATER_DRAG, WATER_DRAG);
+
 impl Physics {
     fn new(mass: f64) -> Physics {
         Physics {
@@ -173,17 +189,19 @@ impl Physics {
         }
     }
 
-    fn accelerate(&mut self, ground: bool, accel: Vector2D<f64>) {
+    fn accelerate(&mut self, ground: bool, water: bool, accel: Vector2D<f64>) {
+        let gravity = if water { WATER_GRAVITY } else { GRAVITY };
         let grav_accel =
-            vec2d!(0.0, if ground { 0.0 } else { self.mass * GRAVITY });
+            vec2d!(0.0, if ground { 0.0 } else { self.mass * gravity });
 
         let drag_x = if ground {
             GROUND_DRAG_VEC
         } else {
             vec2d!(0.0, 0.0)
         };
+        let medium_drag = if water { WATER_DRAG_VEC } else { AIR_DRAG_VEC };
 
-        let drag = self.speed.mul_components(drag_x + AIR_DRAG_VEC);
+        let drag = self.speed.mul_components(drag_x + medium_drag);
 
         self.speed += accel + grav_accel - drag;
     }
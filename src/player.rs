@@ -1,3 +1,4 @@
+use crate::animation::*;
 use crate::controller::*;
 use crate::hitbox::*;
 use crate::level::*;
@@ -17,12 +18,35 @@ use vector2d::Vector2D;
 pub struct Player {
     pub body:          PhysicalBody,
     pub variant:       PlayerVariant,
+    // Frames left after a hit, during which enemies can't hurt the player.
     pub invincibility: u16,
+    // Frames left of the star power, which kills enemies on contact.
+    pub star_power:    u16,
+    pub animation:     Animation,
+    // Enemies stomped since the player last stood on the ground.
+    pub combo:         u8,
+    // Enemies killed with the current star power.
+    pub star_combo:    u8,
+    pub climbing:      bool,
 }
 
 const PLAYER_MASS: f64 = 1.0;
 
-pub const INVINCIBILITY_TIME: u16 = FPS as u16 * 4;
+pub const INVINCIBILITY_TIME: u16 = FPS as u16 * 2;
+pub const STAR_POWER_TIME: u16 = FPS as u16 * 10;
+
+// Colors the player cycles through with the star power, each shown for
+// `STAR_FLASH_FRAMES` frames.
+const STAR_COLORS: [(u8, u8, u8); 4] = [
+    (255, 255, 255),
+    (255, 120, 80),
+    (120, 255, 120),
+    (255, 230, 90),
+];
+const STAR_FLASH_FRAMES: u16 = 3;
+// The player blinks after a hit, hidden for this many frames out of twice
+// as many.
+const BLINK_FRAMES: u16 = 4;
 
 pub const PLAYER_WIDTH: u32 = 48;
 pub const PLAYER_HEIGHT: u32 = 64;
@@ -30,6 +54,13 @@ pub const PLAYER_HEIGHT: u32 = 64;
 pub const BIG_PLAYER_WIDTH: u32 = 64;
 pub const BIG_PLAYER_HEIGHT: u32 = 128;
 
+// Faster than walking is only possible while sprinting.
+const RUN_SPEED: f64 = 7.0;
+
+const CLIMB_SPEED: f64 = 3.0;
+// Horizontal speed of the player letting go of a vine.
+const LET_GO_SPEED: f64 = 4.0;
+
 #[derive(PartialEq, Eq)]
 #[derive(Copy, Clone)]
 #[derive(Debug)]
@@ -41,11 +72,17 @@ pub enum PlayerVariant {
 
 impl Default for Player {
     fn default() -> Player {
-        Player::new(10, LEVEL_HEIGHT as i32 * BLOCK_SIZE as i32 - 256)
+        let (x, y) = DEFAULT_SPAWN;
+        Player::new(x, y)
     }
 }
 
 impl Player {
+    pub fn spawn(level: &Level) -> Player {
+        let (x, y) = level.spawn();
+        Player::new(x, y)
+    }
+
     pub fn new(x: i32, y: i32) -> Player {
         let hitbox = Hitbox::new(x, y, PLAYER_WIDTH, PLAYER_HEIGHT);
         let mass = 0.83;
@@ -56,6 +93,11 @@ impl Player {
             body:          PhysicalBody::new(mass, hitbox),
             variant:       PlayerVariant::Small,
             invincibility: 0,
+            star_power:    0,
+            animation:     Animation::new(Sprite::Player, Clip::Idle),
+            combo:         0,
+            star_combo:    0,
+            climbing:      false,
         }
     }
 
@@ -77,6 +119,48 @@ impl Player {
         self.variant = PlayerVariant::Big;
     }
 
+    pub fn shrink(&mut self) {
+        const HEIGHT_DIFF: u32 = BIG_PLAYER_HEIGHT - PLAYER_HEIGHT;
+        const WIDTH_DIFF: u32 = (BIG_PLAYER_WIDTH - PLAYER_WIDTH) / 2;
+
+        assert_ne!(self.variant, PlayerVariant::Small);
+        let old_hitbox = self.body.hitbox;
+
+        let new_hitbox = Hitbox::new(
+            old_hitbox.x() + WIDTH_DIFF as i32,
+            old_hitbox.y() + HEIGHT_DIFF as i32,
+            PLAYER_WIDTH,
+            PLAYER_HEIGHT,
+        );
+
+        self.body.hitbox = new_hitbox;
+        self.variant = PlayerVariant::Small;
+    }
+
+    pub fn start_star_power(&mut self) {
+        self.star_power = STAR_POWER_TIME;
+        self.star_combo = 0;
+    }
+
+    pub fn has_star_power(&self) -> bool {
+        self.star_power > 0
+    }
+
+    // Big players shrink and can't be hurt again for a while, small ones
+    // die.
+    pub fn hurt(&mut self) {
+        if self.invincibility > 0 || self.has_star_power() {
+            return;
+        }
+
+        if self.is_big() {
+            self.shrink();
+            self.invincibility = INVINCIBILITY_TIME;
+        } else {
+            self.die();
+        }
+    }
+
     pub fn is_big(&self) -> bool {
         self.variant != PlayerVariant::Small
     }
@@ -117,6 +201,70 @@ impl Player {
         self.body.accelerate(accel);
     }
 
+    // Up or down grabs the vine the player touches. Climbing stops when the
+    // vine is gone or the player stands on the ground and presses down.
+    pub fn grab_vine(&mut self, controller: &Controller, vine: Option<Hitbox>) {
+        let vine = match vine {
+            Some(vine) => vine,
+            None => {
+                self.climbing = false;
+                return;
+            },
+        };
+
+        if self.climbing {
+            if self.body.grounded && controller.is_key_active(Key::Down) {
+                self.climbing = false;
+            }
+        } else if controller.was_key_pressed(Key::Up)
+            || controller.was_key_pressed(Key::Down)
+        {
+            let center_x = self.body.hitbox.center().x();
+            self.body.move_by((vine.center().x() - center_x, 0));
+            self.body.stop_x();
+            self.body.stop_y();
+            self.climbing = true;
+        }
+    }
+
+    // The player climbs up to the top of the vine, left or right let go.
+    pub fn climb(&mut self, controller: &Controller, vine: Hitbox) {
+        let x = controller.x_acceleration();
+        if x != 0.0 {
+            self.climbing = false;
+            self.body.move_at(vec2d!(x * LET_GO_SPEED, 0.0));
+            return;
+        }
+
+        let y = if controller.is_key_active(Key::Up) {
+            let to_top = f64::from(vine.top() - self.body.hitbox.top());
+            (-CLIMB_SPEED).max(to_top)
+        } else if controller.is_key_active(Key::Down) {
+            CLIMB_SPEED
+        } else {
+            0.0
+        };
+        self.body.move_at(vec2d!(0.0, y));
+    }
+
+    // Every press of up is a stroke upwards, close to the surface it jumps
+    // out of the water.
+    pub fn swim(&mut self, controller: &Controller, surface: bool) {
+        const SWIM_ACCELERATION: f64 = 0.5;
+        const STROKE_SPEED: f64 = -6.0;
+        const LEAP_SPEED: f64 = -11.0;
+
+        let x_accel = SWIM_ACCELERATION * controller.x_acceleration();
+        let y_accel = if !controller.was_key_pressed(Key::Up) {
+            0.0
+        } else if surface {
+            LEAP_SPEED - self.body.speed_y()
+        } else {
+            STROKE_SPEED - self.body.speed_y()
+        };
+        self.body.accelerate(vec2d!(x_accel, y_accel));
+    }
+
     pub fn rect(&self) -> Rect {
         self.body.hitbox
     }
@@ -126,8 +274,8 @@ impl Player {
     }
 
     pub fn stick_camera(&self, cam: &mut Camera) {
-        let x = self.body.hitbox.x() - SCREEN_WIDTH as i32 / 2;
-        let y = self.body.hitbox.y() - SCREEN_HEIGHT as i32 / 2;
+        let x = self.body.hitbox.x() - LOGICAL_WIDTH as i32 / 2;
+        let y = self.body.hitbox.y() - LOGICAL_HEIGHT as i32 / 2;
         cam.move_to((x, y));
     }
 
@@ -135,73 +283,79 @@ impl Player {
         self.body.hitbox.top_left().into()
     }
 
-    pub fn texture_id(&self) -> TextureId {
-        if !self.body.grounded {
-            match self.variant {
-                PlayerVariant::Small => TextureId::PlayerJumping,
-                PlayerVariant::Big => TextureId::BigPlayerJumping,
-                PlayerVariant::CanShoot => unimplemented!(),
-            }
-        } else if self.body.is_still() {
-            match self.variant {
-                PlayerVariant::Small => TextureId::PlayerStanding,
-                PlayerVariant::Big => TextureId::BigPlayerStanding,
-                PlayerVariant::CanShoot => unimplemented!(),
-            }
+    pub fn sprite(&self) -> Sprite {
+        match self.variant {
+            PlayerVariant::Small => Sprite::Player,
+            PlayerVariant::Big => Sprite::BigPlayer,
+            PlayerVariant::CanShoot => unimplemented!(),
+        }
+    }
+
+    pub fn die(&mut self) {
+        self.animation.play(Clip::Death);
+    }
+
+    pub fn is_dying(&self) -> bool {
+        self.animation.clip() == Clip::Death
+    }
+
+    // Follows the movement of the player, except for the death clip which
+    // plays until its end.
+    pub fn update_animation(
+        &mut self,
+        controller: &Controller,
+        res: &ResourceManager,
+    ) -> Option<AnimationEvent> {
+        self.animation.set_sprite(self.sprite());
+        self.animation.set_tint(self.star_tint());
+        let transition = if self.is_dying() {
+            None
         } else {
-            match self.variant {
-                PlayerVariant::Small => TextureId::PlayerRunning,
-                PlayerVariant::Big => TextureId::BigPlayerRunning,
-                PlayerVariant::CanShoot => unimplemented!(),
-            }
+            self.animation.play(self.movement_clip(controller))
+        };
+        self.animation.update(res).or(transition)
+    }
+
+    fn star_tint(&self) -> Option<Color> {
+        if !self.has_star_power() {
+            return None;
+        }
+        let index = self.star_power / STAR_FLASH_FRAMES;
+        let (r, g, b) = STAR_COLORS[usize::from(index) % STAR_COLORS.len()];
+        Some(Color::RGB(r, g, b))
+    }
+
+    fn movement_clip(&self, controller: &Controller) -> Clip {
+        let speed = self.body.speed_x();
+        if self.climbing {
+            Clip::Climb
+        } else if self.body.submerged && !self.body.grounded {
+            Clip::Swim
+        } else if !self.body.grounded {
+            Clip::Jump
+        } else if self.body.is_still_x() {
+            Clip::Idle
+        } else if controller.x_acceleration() * speed < 0.0 {
+            Clip::Skid
+        } else if speed.abs() > RUN_SPEED {
+            Clip::Run
+        } else {
+            Clip::Walk
         }
     }
 }
 
 impl Drawable for Player {
     fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
-        const MOVEMENT_THRESHOLD: f64 = 0.3;
         let player = data.object;
-        let variant = player.texture_id();
-
-        let flip = player.body.x_direction() == XDirection::Right;
-
-        let info = res.entity_texture_info(variant);
-        let (x, y) = player.position();
-        let (off_x, off_y) = info.hitbox_offset();
-        let (x, y) = (x + off_x, y + off_y);
-
-        let tick = data.tick + player.body.speed_x().abs() as u32;
-        let sprite_x = info.frame_index(tick) * info.width;
-        let src_region = rect!(sprite_x, 0, info.width, info.height);
-
-        let (cam_x, cam_y) = data.camera.translate_coords((x, y));
-        let width = (info.width as f64 * data.scale) as u32;
-        let height = (info.height as f64 * data.scale) as u32;
-        let dest = rect!(cam_x, cam_y, width, height);
-
-        let path = info.path.clone();
-
-        data.renderer
-            .canvas
-            .copy_ex(
-                &res.texture(&path),
-                src_region,
-                dest,
-                0.0,
-                Point::new(0, 0),
-                flip,
-                false,
-            )
-            .expect("Failed to draw the player!");
-
-        if player.invincibility > 0 {
-            let progress =
-                (player.invincibility as f64) / INVINCIBILITY_TIME as f64;
-
-            let width = (64.0 * progress) as u32;
-            let bar = rect!(cam_x, cam_y, width, 10);
-            data.renderer.draw(&bar).show(res);
+        if player.invincibility / BLINK_FRAMES % 2 == 1 {
+            return;
         }
+
+        let direction = player.body.x_direction();
+        pass_draw!(data, &player.animation)
+            .position(player.position())
+            .mode(DrawMode::EntityDirection(direction))
+            .show(res);
     }
 }
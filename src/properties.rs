@@ -0,0 +1,140 @@
+use crate::entity::*;
+use crate::render::*;
+use crate::resource::*;
+use crate::utility::*;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+const ROW_WIDTH: u32 = 260;
+const ROW_HEIGHT: u32 = 32;
+const SPEED_STEP: f64 = 0.25;
+
+// Parameter of an entity that can be changed in the editor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Property {
+    Direction,
+    Speed,
+    Warp,
+}
+
+// List of the properties of the picked entity, drawn as rows which change
+// the value when clicked.
+pub struct PropertiesPanel {
+    entity:   EntityPrototype,
+    position: (i32, i32),
+}
+
+impl Property {
+    pub fn of(kind: EntityType) -> &'static [Property] {
+        match kind {
+            EntityType::Enemy(..) => &[Property::Direction, Property::Speed],
+            EntityType::Warp => &[Property::Warp],
+            _ => &[],
+        }
+    }
+
+    pub fn label(self, properties: &EntityProperties) -> String {
+        match self {
+            Property::Direction => {
+                let direction = match properties.direction {
+                    XDirection::Left => "LEFT",
+                    XDirection::Right => "RIGHT",
+                    XDirection::Still => "RANDOM",
+                };
+                format!("DIRECTION: {}", direction)
+            },
+            Property::Speed => format!("SPEED: {:.2}", properties.speed),
+            Property::Warp => match properties.warp {
+                Some(link) => format!("WARP LINK: {}", link),
+                None => String::from("WARP LINK: NONE"),
+            },
+        }
+    }
+
+    // Cycles through the values of the property.
+    pub fn change(self, properties: &mut EntityProperties, forward: bool) {
+        match self {
+            Property::Direction => {
+                properties.direction = match (properties.direction, forward) {
+                    (XDirection::Still, true) => XDirection::Left,
+                    (XDirection::Left, true) => XDirection::Right,
+                    (XDirection::Right, true) => XDirection::Still,
+                    (XDirection::Still, false) => XDirection::Right,
+                    (XDirection::Right, false) => XDirection::Left,
+                    (XDirection::Left, false) => XDirection::Still,
+                };
+            },
+            Property::Speed => {
+                let step = if forward { SPEED_STEP } else { -SPEED_STEP };
+                properties.speed = (properties.speed + step)
+                    .clamp(MIN_ENTITY_SPEED, MAX_ENTITY_SPEED);
+            },
+            Property::Warp => {
+                properties.warp = match (properties.warp, forward) {
+                    (None, true) => Some(0),
+                    (Some(MAX_WARP_LINK), true) => None,
+                    (Some(link), true) => Some(link + 1),
+                    (None, false) => Some(MAX_WARP_LINK),
+                    (Some(0), false) => None,
+                    (Some(link), false) => Some(link - 1),
+                };
+            },
+        }
+    }
+}
+
+impl PropertiesPanel {
+    // The panel is placed in the bottom right corner of the area left of
+    // the given edge.
+    pub fn new(entity: EntityPrototype, right_edge: i32) -> PropertiesPanel {
+        const MARGIN: i32 = 10;
+        let rows = Property::of(entity.kind).len() as u32;
+        let x = right_edge - ROW_WIDTH as i32 - MARGIN;
+        let y = LOGICAL_HEIGHT as i32 - (rows * ROW_HEIGHT) as i32 - MARGIN;
+
+        PropertiesPanel {
+            entity,
+            position: (x, y),
+        }
+    }
+
+    fn rows(&self) -> Vec<(Property, Rect)> {
+        let (x, y) = self.position;
+        Property::of(self.entity.kind)
+            .iter()
+            .enumerate()
+            .map(|(i, &property)| {
+                let row_y = y + (i as u32 * ROW_HEIGHT) as i32;
+                (property, rect!(x, row_y, ROW_WIDTH, ROW_HEIGHT))
+            })
+            .collect()
+    }
+
+    pub fn property_at(&self, pos: (i32, i32)) -> Option<Property> {
+        self.rows()
+            .into_iter()
+            .find(|(_, rect)| rect.contains_point(pos))
+            .map(|(property, _)| property)
+    }
+}
+
+impl Drawable for PropertiesPanel {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        const PADDING: i32 = 6;
+
+        for (property, rect) in data.object.rows() {
+            data.renderer.canvas.set_draw_color(Color::RGBA(0, 0, 0, 192));
+            data.renderer.canvas.fill_rect(rect).unwrap();
+            data.renderer.canvas.set_draw_color(Color::RGB(80, 80, 80));
+            data.renderer.canvas.draw_rect(rect).unwrap();
+
+            let label = property.label(&data.object.entity.properties);
+            data.renderer
+                .draw(&text!(&label))
+                .position((rect.x() + PADDING, rect.y() + PADDING))
+                .scale(0.2)
+                .show(res);
+        }
+    }
+}
@@ -1,31 +1,53 @@
 use crate::block::*;
 use crate::level::*;
 use crate::resource::*;
+use crate::settings::*;
 use crate::utility::*;
 
-use sdl2::pixels::Color;
+use sdl2::image::LoadTexture;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::{Point, Rect};
-use sdl2::render::{TextureCreator, TextureQuery};
-use sdl2::video::WindowContext;
+use sdl2::render::{BlendMode, Texture, TextureCreator, TextureQuery};
+use sdl2::surface::{Surface, SurfaceContext};
+use sdl2::video::{FullscreenType, Window, WindowContext};
 
 use std::fmt::Debug;
+use std::ops::Range;
 
-type Canvas = sdl2::render::Canvas<sdl2::video::Window>;
+pub type WindowCanvas = sdl2::render::Canvas<Window>;
+pub type SurfaceCanvas = sdl2::render::Canvas<Surface<'static>>;
+
+// The game draws into its window, the offscreen renderer into a surface
+// in memory. Both expose the subset of the SDL canvas used by `Drawable`s.
+pub enum Canvas {
+    Window(WindowCanvas),
+    Surface(SurfaceCanvas),
+}
+
+// Textures can only be drawn by the canvas whose creator made them.
+pub enum TextureFactory {
+    Window(TextureCreator<WindowContext>),
+    Surface(TextureCreator<SurfaceContext<'static>>),
+}
 
 pub const FPS: u32 = 60;
 
-pub const SCREEN_WIDTH: u32 = 1280;
-pub const SCREEN_HEIGHT: u32 = 720;
+// Resolution the game is laid out in. The canvas scales it to the actual
+// window size, adding black bars when the aspect ratios differ.
+pub const LOGICAL_WIDTH: u32 = 1280;
+pub const LOGICAL_HEIGHT: u32 = 720;
 
 pub struct Renderer {
     pub canvas:          Canvas,
-    pub texture_creator: TextureCreator<WindowContext>,
+    pub texture_creator: TextureFactory,
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct Camera {
-    x: i32,
-    y: i32,
+    x:      i32,
+    y:      i32,
+    width:  u32,
+    height: u32,
 }
 
 #[derive(Copy, Clone)]
@@ -46,6 +68,7 @@ pub struct DrawCall<'a, T: Drawable> {
     pub mode:     DrawMode,
 }
 
+#[derive(Clone, Copy)]
 pub struct PartialDrawCall {
     pub tick:     u32,
     pub scale:    f64,
@@ -137,6 +160,18 @@ impl PartialDrawCall {
     }
 }
 
+impl<T: Drawable> From<&DrawCall<'_, T>> for PartialDrawCall {
+    fn from(call: &DrawCall<T>) -> PartialDrawCall {
+        PartialDrawCall {
+            tick:     call.tick,
+            scale:    call.scale,
+            position: call.position,
+            camera:   call.camera,
+            mode:     call.mode,
+        }
+    }
+}
+
 impl Drawable for Rect {
     fn show(data: DrawCall<Self>, _res: &mut ResourceManager) {
         let (shift_x, shift_y) = data.position;
@@ -217,25 +252,247 @@ impl Text<'_> {
     }
 }
 
+macro_rules! on_canvas {
+    ($canvas:expr, $inner:ident => $body:expr) => {
+        match $canvas {
+            Canvas::Window($inner) => $body,
+            Canvas::Surface($inner) => $body,
+        }
+    };
+}
+
+impl Canvas {
+    pub fn texture_creator(&self) -> TextureFactory {
+        match self {
+            Canvas::Window(canvas) => {
+                TextureFactory::Window(canvas.texture_creator())
+            },
+            Canvas::Surface(canvas) => {
+                TextureFactory::Surface(canvas.texture_creator())
+            },
+        }
+    }
+
+    pub fn window_mut(&mut self) -> Option<&mut Window> {
+        match self {
+            Canvas::Window(canvas) => Some(canvas.window_mut()),
+            Canvas::Surface(..) => None,
+        }
+    }
+
+    pub fn set_logical_size(&mut self, width: u32, height: u32) -> Result<()> {
+        on_canvas!(self, canvas => canvas.set_logical_size(width, height)?);
+        Ok(())
+    }
+
+    pub fn logical_size(&self) -> (u32, u32) {
+        on_canvas!(self, canvas => canvas.logical_size())
+    }
+
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        on_canvas!(self, canvas => canvas.set_blend_mode(mode))
+    }
+
+    pub fn set_draw_color(&mut self, color: Color) {
+        on_canvas!(self, canvas => canvas.set_draw_color(color))
+    }
+
+    pub fn clear(&mut self) {
+        on_canvas!(self, canvas => canvas.clear())
+    }
+
+    // Drawing is limited to the rectangle until it's reset with `None`.
+    pub fn set_clip_rect<R>(&mut self, rect: R)
+    where
+        R: Into<Option<Rect>>,
+    {
+        on_canvas!(self, canvas => canvas.set_clip_rect(rect))
+    }
+
+    pub fn present(&mut self) {
+        on_canvas!(self, canvas => canvas.present())
+    }
+
+    pub fn fill_rect<R>(&mut self, rect: R) -> std::result::Result<(), String>
+    where
+        R: Into<Option<Rect>>,
+    {
+        on_canvas!(self, canvas => canvas.fill_rect(rect))
+    }
+
+    pub fn draw_rect(&mut self, rect: Rect) -> std::result::Result<(), String> {
+        on_canvas!(self, canvas => canvas.draw_rect(rect))
+    }
+
+    pub fn draw_line<P1, P2>(
+        &mut self,
+        start: P1,
+        end: P2,
+    ) -> std::result::Result<(), String>
+    where
+        P1: Into<Point>,
+        P2: Into<Point>,
+    {
+        on_canvas!(self, canvas => canvas.draw_line(start, end))
+    }
+
+    pub fn copy<R1, R2>(
+        &mut self,
+        texture: &Texture,
+        src: R1,
+        dst: R2,
+    ) -> std::result::Result<(), String>
+    where
+        R1: Into<Option<Rect>>,
+        R2: Into<Option<Rect>>,
+    {
+        on_canvas!(self, canvas => canvas.copy(texture, src, dst))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn copy_ex<R1, R2, P>(
+        &mut self,
+        texture: &Texture,
+        src: R1,
+        dst: R2,
+        angle: f64,
+        center: P,
+        flip_horizontal: bool,
+        flip_vertical: bool,
+    ) -> std::result::Result<(), String>
+    where
+        R1: Into<Option<Rect>>,
+        R2: Into<Option<Rect>>,
+        P: Into<Option<Point>>,
+    {
+        on_canvas!(self, canvas => {
+            canvas.copy_ex(
+                texture,
+                src,
+                dst,
+                angle,
+                center,
+                flip_horizontal,
+                flip_vertical,
+            )
+        })
+    }
+}
+
+impl TextureFactory {
+    pub fn load_texture(&self, path: &str) -> Result<Texture<'_>> {
+        let texture = match self {
+            TextureFactory::Window(creator) => creator.load_texture(path)?,
+            TextureFactory::Surface(creator) => creator.load_texture(path)?,
+        };
+        Ok(texture)
+    }
+
+    pub fn create_texture_from_surface(
+        &self,
+        surface: &Surface,
+    ) -> Result<Texture<'_>> {
+        let texture = match self {
+            TextureFactory::Window(creator) => {
+                creator.create_texture_from_surface(surface)?
+            },
+            TextureFactory::Surface(creator) => {
+                creator.create_texture_from_surface(surface)?
+            },
+        };
+        Ok(texture)
+    }
+
+    pub fn create_texture_target(
+        &self,
+        format: PixelFormatEnum,
+        width: u32,
+        height: u32,
+    ) -> Result<Texture<'_>> {
+        let texture = match self {
+            TextureFactory::Window(creator) => {
+                creator.create_texture_target(format, width, height)?
+            },
+            TextureFactory::Surface(creator) => {
+                creator.create_texture_target(format, width, height)?
+            },
+        };
+        Ok(texture)
+    }
+}
+
 impl Renderer {
-    pub fn new(canvas: Canvas) -> Renderer {
+    pub fn new(canvas: WindowCanvas) -> Result<Renderer> {
+        Renderer::from_canvas(Canvas::Window(canvas))
+    }
+
+    // Software renderer drawing into a surface of the given size, which
+    // doesn't need a window or even an initialized video subsystem.
+    pub fn offscreen(width: u32, height: u32) -> Result<Renderer> {
+        let surface = Surface::new(width, height, PixelFormatEnum::RGBA8888)?;
+        let mut canvas = surface.into_canvas()?;
+        canvas.set_blend_mode(BlendMode::Blend);
+
+        let mut renderer = Renderer::from_canvas(Canvas::Surface(canvas))?;
+        renderer.canvas.set_logical_size(width, height)?;
+        Ok(renderer)
+    }
+
+    fn from_canvas(mut canvas: Canvas) -> Result<Renderer> {
+        if let Canvas::Window(..) = canvas {
+            canvas.set_logical_size(LOGICAL_WIDTH, LOGICAL_HEIGHT)?;
+        }
         let creator = canvas.texture_creator();
-        Renderer {
+        Ok(Renderer {
             canvas,
             texture_creator: creator,
+        })
+    }
+
+    // Hands over the image drawn by an offscreen renderer.
+    pub fn into_surface(self) -> Result<Surface<'static>> {
+        match self.canvas {
+            Canvas::Surface(canvas) => Ok(canvas.into_surface()),
+            Canvas::Window(..) => {
+                Err("Only offscreen renderers draw into a surface!".into())
+            },
         }
     }
 
+    pub fn logical_size(&self) -> (u32, u32) {
+        self.canvas.logical_size()
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        let (width, height) = self.logical_size();
+        (width as i32 / 2, height as i32 / 2)
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) -> Result<()> {
+        let window = match self.canvas.window_mut() {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+
+        if settings.fullscreen {
+            window.set_fullscreen(FullscreenType::Desktop)?;
+        } else {
+            window.set_fullscreen(FullscreenType::Off)?;
+            let (width, height) = settings.window_size;
+            window.set_size(width, height)?;
+        }
+        Ok(())
+    }
+
     pub fn clear(&mut self, color: Color) {
         self.canvas.set_draw_color(color);
         self.canvas.clear();
     }
 
     pub fn fill(&mut self, color: Color) {
+        let (width, height) = self.logical_size();
         self.canvas.set_draw_color(color);
-        self.canvas
-            .fill_rect(rect!(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT))
-            .unwrap();
+        self.canvas.fill_rect(rect!(0, 0, width, height)).unwrap();
     }
 
     pub fn draw<'a, T: Drawable>(&'a mut self, obj: &'a T) -> DrawCall<T> {
@@ -335,16 +592,21 @@ impl Default for Camera {
 
 impl Camera {
     pub fn new(x: i32, y: i32) -> Camera {
-        Camera { x, y }
-    }
-
-    pub fn cloud_camera(self) -> Camera {
         Camera {
-            x: self.x / 2,
-            y: self.y
+            x,
+            y,
+            width: LOGICAL_WIDTH,
+            height: LOGICAL_HEIGHT,
         }
     }
 
+    // Size of the area the camera looks at, the whole screen by default.
+    pub fn view(mut self, width: u32, height: u32) -> Camera {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
     pub fn move_to(&mut self, pos: (i32, i32)) {
         self.x = 0;
         self.y = 0;
@@ -355,22 +617,21 @@ impl Camera {
         self.x += amount.0;
         self.y += amount.1;
 
-        const MAX_X: i32 =
-            (LEVEL_WIDTH as u32 * BLOCK_SIZE - SCREEN_WIDTH) as i32;
-
-        const MAX_Y: i32 =
-            (LEVEL_HEIGHT as u32 * BLOCK_SIZE - SCREEN_HEIGHT) as i32;
+        let max_x =
+            (LEVEL_WIDTH as u32 * BLOCK_SIZE).saturating_sub(self.width) as i32;
+        let max_y = (LEVEL_HEIGHT as u32 * BLOCK_SIZE)
+            .saturating_sub(self.height) as i32;
 
         if self.x <= 0 {
             self.x = 0;
-        } else if self.x > MAX_X {
-            self.x = MAX_X;
+        } else if self.x > max_x {
+            self.x = max_x;
         }
 
         if self.y <= 0 {
             self.y = 0;
-        } else if self.y > MAX_Y {
-            self.y = MAX_Y;
+        } else if self.y > max_y {
+            self.y = max_y;
         }
     }
 
@@ -388,17 +649,40 @@ impl Camera {
 
     pub fn on_screen(self, (x, y): (i32, i32)) -> bool {
         x >= 0
-            && x <= SCREEN_WIDTH as i32
+            && x <= LOGICAL_WIDTH as i32
             && y >= 0
-            && y <= SCREEN_HEIGHT as i32
+            && y <= LOGICAL_HEIGHT as i32
+    }
+
+    // Ranges of block columns and rows that are visible on the screen.
+    // The margin accounts for tiles that are larger than a single block.
+    pub fn visible_blocks(
+        self,
+        margin: usize,
+    ) -> (Range<usize>, Range<usize>) {
+        fn range(
+            from: i32,
+            length: u32,
+            margin: usize,
+            max: usize,
+        ) -> Range<usize> {
+            let from = from.max(0) as usize;
+            let first = (from / BLOCK_SIZE as usize).saturating_sub(margin);
+            let last = (from + length as usize) / BLOCK_SIZE as usize + 1;
+            first.min(max)..last.min(max)
+        }
+
+        let cols = range(self.x, self.width, margin, LEVEL_WIDTH);
+        let rows = range(self.y, self.height, margin, LEVEL_HEIGHT);
+        (cols, rows)
     }
 
     pub fn in_view(self, rect: Rect) -> bool {
         let cam_rect = Rect::new(
             self.x - 1,
             self.y - 1,
-            SCREEN_WIDTH + 1,
-            SCREEN_HEIGHT + 1,
+            self.width + 1,
+            self.height + 1,
         );
         cam_rect.contains_rect(rect) || cam_rect.has_intersection(rect)
     }
@@ -418,7 +702,7 @@ impl Drawable for Text<'_> {
             .map_err(|err| err.to_string())
             .and_then(|surface| {
                 creator
-                    .create_texture_from_surface(surface)
+                    .create_texture_from_surface(&surface)
                     .map_err(|err| err.to_string())
             })
             .unwrap_or_else(|err| panic_with_messagebox!("{}", err));
@@ -437,13 +721,14 @@ impl Drawable for Text<'_> {
 }
 
 pub fn draw_grid(renderer: &mut Renderer, camera: Camera) {
+    let (width, height) = renderer.logical_size();
     renderer.canvas.set_draw_color(Color::RGB(50, 50, 50));
-    let cols = (camera.x + SCREEN_WIDTH as i32) / BLOCK_SIZE as i32;
+    let cols = (camera.x + width as i32) / BLOCK_SIZE as i32;
 
     for col in 0..=cols {
         let x = col * BLOCK_SIZE as i32 - camera.x;
         let from = Point::new(x, 0);
-        let to = Point::new(x, SCREEN_HEIGHT as i32);
+        let to = Point::new(x, height as i32);
         renderer.canvas.draw_line(from, to).unwrap();
     }
 
@@ -451,7 +736,7 @@ pub fn draw_grid(renderer: &mut Renderer, camera: Camera) {
     for row in 0..=rows {
         let y = row * BLOCK_SIZE as i32 - camera.y;
         let from = Point::new(0, y);
-        let to = Point::new(SCREEN_WIDTH as i32, y);
+        let to = Point::new(width as i32, y);
         renderer.canvas.draw_line(from, to).unwrap();
     }
 }
@@ -1,33 +1,71 @@
 use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::convert::TryInto;
 use std::fs;
 use std::hash::Hash;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::SystemTime;
 
-use sdl2::image::LoadTexture;
-use sdl2::render::{Texture, TextureCreator};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture};
 use sdl2::ttf::{Font, Sdl2TtfContext};
-use sdl2::video::WindowContext;
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::animation::*;
+use crate::atlas::*;
 use crate::background::*;
 use crate::block::*;
 use crate::entity::*;
+use crate::error::*;
 use crate::level::*;
+use crate::parallax::*;
+use crate::particle::*;
+use crate::paths::*;
+use crate::render::*;
+use crate::settings::*;
+use crate::stamp::*;
 use crate::texture_id::*;
+use crate::watcher::*;
 use crate::utility::*;
 
 pub struct ResourceManager<'a> {
-    res_path:     PathBuf,
+    paths:        GamePaths,
+    ttf:          &'a Sdl2TtfContext,
     font:         Font<'a, 'static>,
-    textures:     TextureCache<'a, WindowContext>,
-    texture_info: TexturePaths,
+    textures:     TextureCache<'a>,
+    texture_info: TextureTable,
+    atlas:        TextureAtlas<'a>,
+    layer_cache:  HashMap<(usize, usize), Texture<'a>>,
+    placeholder:  Rc<Texture<'a>>,
+    thumbnails:   HashMap<String, (SystemTime, Texture<'a>)>,
+    // Copies of shared textures, which can be tinted without changing the
+    // colors of the original.
+    tinted:       HashMap<String, Texture<'a>>,
+    level_pack:   String,
+    watcher:      FileWatcher,
 }
 
-pub type TextureCache<'a, T> =
-    ResourceCache<'a, String, Texture<'a>, TextureCreator<T>>;
+// Files that changed on the disk since the last check.
+#[derive(Default)]
+pub struct ResourceChanges {
+    pub textures: bool,
+    pub levels:   Vec<String>,
+    // the reload key reloads everything, even levels with unsaved changes
+    pub forced:   bool,
+}
+
+// Level file in the levels directory along with the time of its last change.
+pub struct LevelFile {
+    pub name:     String,
+    pub modified: SystemTime,
+}
+
+pub type TextureCache<'a> =
+    ResourceCache<'a, String, Texture<'a>, TextureFactory>;
 
 #[derive(Deserialize)]
 #[derive(Clone)]
@@ -43,6 +81,8 @@ pub struct TextureInfo {
     pub themed:    bool,
     #[serde(default = "default_hitbox_offset")]
     pub hitbox_offset: (i32, i32),
+    #[serde(skip)]
+    atlas_origin:      (i32, i32),
 }
 
 #[derive(Deserialize)]
@@ -57,6 +97,26 @@ struct TexturePaths {
     blocks:     HashMap<BlockType, TextureInfo>,
     background: HashMap<BackgroundElement, TextureInfo>,
     entities:   HashMap<TextureId, TextureInfo>,
+    #[serde(default)]
+    parallax:   HashMap<LevelTheme, Vec<ParallaxLayer>>,
+    #[serde(default)]
+    animations: HashMap<Sprite, HashMap<Clip, AnimationClip>>,
+    #[serde(default)]
+    emitters:   HashMap<Emitter, EmitterInfo>,
+}
+
+// Tile textures are looked up for every visible cell each frame, so they
+// are indexed by their discriminant instead of hashed.
+struct TextureTable {
+    blocks:      Vec<Option<TextureInfo>>,
+    background:  Vec<Option<TextureInfo>>,
+    entities:    HashMap<TextureId, TextureInfo>,
+    // Shared with the parallax drawing, which needs the resource manager
+    // while it goes through the layers.
+    parallax:    HashMap<LevelTheme, Rc<[ParallaxLayer]>>,
+    animations:  HashMap<Sprite, HashMap<Clip, AnimationClip>>,
+    emitters:    HashMap<Emitter, EmitterInfo>,
+    placeholder: TextureInfo,
 }
 
 pub struct ResourceCache<'a, Key, Resource, Loader>
@@ -85,6 +145,18 @@ where
         }
     }
 
+    pub fn loader(&self) -> &'a Loader {
+        self.loader
+    }
+
+    pub fn insert(&mut self, key: Key, resource: Rc<Resource>) {
+        self.cache.insert(key, resource);
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
     pub fn load<Details>(&mut self, details: &Details) -> Result<Rc<Resource>>
     where
         Loader: ResourceLoader<'a, Resource, Args = Details>,
@@ -102,161 +174,670 @@ where
     }
 }
 
-impl<'a, T> ResourceLoader<'a, Texture<'a>> for TextureCreator<T> {
+impl<'a> ResourceLoader<'a, Texture<'a>> for TextureFactory {
     type Args = str;
 
     fn load(&'a self, path: &str) -> Result<Texture> {
-        let texture = self.load_texture(path)?;
+        self.load_texture(path)
+    }
+}
+
+impl From<TexturePaths> for TextureTable {
+    fn from(paths: TexturePaths) -> TextureTable {
+        let mut blocks = vec![None; BlockType::Air as usize + 1];
+        for (block, info) in paths.blocks.into_iter() {
+            blocks[block as usize] = Some(info);
+        }
+
+        let mut background = vec![None; BackgroundElement::Air as usize + 1];
+        for (bg, info) in paths.background.into_iter() {
+            background[bg as usize] = Some(info);
+        }
+
+        let placeholder = TextureInfo {
+            path:          String::from(PLACEHOLDER),
+            width:         PLACEHOLDER_SIZE,
+            height:        PLACEHOLDER_SIZE,
+            animation:     default_animation(),
+            themed:        false,
+            hitbox_offset: default_hitbox_offset(),
+            atlas_origin:  (0, 0),
+        };
+
+        TextureTable {
+            blocks,
+            background,
+            entities: paths.entities,
+            parallax: paths
+                .parallax
+                .into_iter()
+                .map(|(theme, layers)| (theme, layers.into()))
+                .collect(),
+            animations: paths.animations,
+            emitters: paths.emitters,
+            placeholder,
+        }
+    }
+}
+
+impl TextureTable {
+    fn infos_mut(&mut self) -> impl Iterator<Item = &mut TextureInfo> {
+        self.blocks
+            .iter_mut()
+            .chain(self.background.iter_mut())
+            .filter_map(Option::as_mut)
+            .chain(self.entities.values_mut())
+            .chain(std::iter::once(&mut self.placeholder))
+    }
+}
+
+impl<'a> ResourceManager<'a> {
+    pub fn create_layer_texture(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> Result<Texture<'a>> {
+        let mut texture = self.textures.loader().create_texture_target(
+            PixelFormatEnum::RGBA8888,
+            width,
+            height,
+        )?;
+        texture.set_blend_mode(BlendMode::Blend);
         Ok(texture)
     }
+
+    // The cache only ever holds the chunks of a single level.
+    pub fn insert_layer_chunk(
+        &mut self,
+        level: usize,
+        chunk: usize,
+        texture: Texture<'a>,
+    ) {
+        self.layer_cache.retain(|&(id, _), _| id == level);
+        self.layer_cache.insert((level, chunk), texture);
+    }
+
+    // Textures that fail to load are replaced by the placeholder, the error
+    // is reported just once as the placeholder is cached in their place.
+    pub fn texture(&mut self, name: &str) -> Rc<Texture<'a>> {
+        match self.try_texture(name) {
+            Ok(texture) => texture,
+            Err(err) => {
+                eprintln!("{}", err);
+                let path = self.texture_path(name);
+                self.textures.insert(path, self.placeholder.clone());
+                self.placeholder.clone()
+            },
+        }
+    }
+
+    // The colors of the texture multiplied by the color. Returns `None` if
+    // the texture can't be loaded.
+    pub fn tinted_texture(
+        &mut self,
+        name: &str,
+        color: Color,
+    ) -> Option<&Texture<'a>> {
+        let path = self.texture_path(name);
+        if !self.tinted.contains_key(&path) {
+            let texture = self.textures.loader().load(path.as_str()).ok()?;
+            self.tinted.insert(path.clone(), texture);
+        }
+
+        let texture = self.tinted.get_mut(&path)?;
+        texture.set_color_mod(color.r, color.g, color.b);
+        Some(texture)
+    }
+
+    pub fn try_texture(
+        &mut self,
+        name: &str,
+    ) -> ResourceResult<Rc<Texture<'a>>> {
+        let path = self.texture_path(name);
+        self.textures.load(path.as_str()).map_err(|err| {
+            ResourceError::Texture {
+                name:    String::from(name),
+                message: err.to_string(),
+            }
+        })
+    }
+
+    fn texture_path(&self, name: &str) -> String {
+        let path = self.paths.resources.join(format!("textures/{}.png", name));
+        path.to_string_lossy().into_owned()
+    }
+
+    // The texture info and the atlas are replaced only when both load, so
+    // that a mistake in a file being edited keeps the old textures.
+    pub fn reload_textures(&mut self) -> Result<()> {
+        let loader = self.textures.loader();
+        let (texture_info, atlas) =
+            load_textures(loader, &self.paths.resources)?;
+        self.texture_info = texture_info;
+        self.atlas = atlas;
+        self.textures.clear();
+        self.tinted.clear();
+        self.clear_render_targets();
+        Ok(())
+    }
+
+    // Textures rendered by the game itself are drawn again when they are
+    // needed.
+    pub fn clear_render_targets(&mut self) {
+        self.layer_cache.clear();
+        self.thumbnails.clear();
+    }
+
+    // Textures are reloaded right away, changed levels are left to the
+    // activities which use them.
+    pub fn poll_changes(&mut self) -> ResourceChanges {
+        let changes = classify_changes(&self.watcher.poll());
+        if changes.textures {
+            if let Err(err) = self.reload_textures() {
+                eprintln!("Failed to reload textures: {}", err);
+            }
+        }
+        changes
+    }
+
+    pub fn reload_all(&mut self) -> ResourceChanges {
+        self.watcher.rescan();
+        if let Err(err) = self.reload_textures() {
+            eprintln!("Failed to reload textures: {}", err);
+        }
+        ResourceChanges {
+            textures: true,
+            levels:   vec![],
+            forced:   true,
+        }
+    }
+
+    pub fn paths(&self) -> &GamePaths {
+        &self.paths
+    }
+
+    pub fn ttf(&self) -> &'a Sdl2TtfContext {
+        self.ttf
+    }
+
+    // Thumbnails are kept until the level file changes.
+    pub fn insert_thumbnail(
+        &mut self,
+        level: &LevelFile,
+        texture: Texture<'a>,
+    ) {
+        self.thumbnails
+            .insert(level.name.clone(), (level.modified, texture));
+    }
 }
 
 impl ResourceManager<'_> {
     pub fn new<'a>(
-        cache: TextureCache<'a, WindowContext>,
+        cache: TextureCache<'a>,
         ttf: &'a Sdl2TtfContext,
+        paths: GamePaths,
     ) -> Result<ResourceManager<'a>> {
-        let res_path = get_base_path()?.join("resources/");
+        let res_path = &paths.resources;
 
         let font_path = res_path.join("font.ttf");
         let mut font = ttf.load_font(font_path, 128)?;
         font.set_style(sdl2::ttf::FontStyle::NORMAL);
 
-        let texture_info =
-            fs::read_to_string(res_path.join("textures/info.json"))
-                .map_err(|err| err.to_string())
-                .and_then(|info_str| {
-                    serde_json::from_str(&info_str)
-                        .map_err(|err| err.to_string())
-                })
-                .unwrap_or_else(|err| {
-                    panic_with_messagebox!(
-                        "Failed to load textures info due to an error in the \
-                         JSON file:\n{}",
-                        err
-                    )
-                });
+        let (texture_info, atlas) = load_textures(cache.loader(), res_path)?;
+        let mut watched = vec![res_path.join("textures/")];
+        watched.extend(paths.file_dirs("levels"));
+        let watcher = FileWatcher::new(watched);
+
+        let placeholder = cache
+            .loader()
+            .create_texture_from_surface(&placeholder_surface()?)?;
 
         Ok(ResourceManager {
-            res_path,
+            paths,
+            ttf,
             font,
             textures: cache,
             texture_info,
+            atlas,
+            layer_cache: HashMap::new(),
+            placeholder: Rc::new(placeholder),
+            thumbnails: HashMap::new(),
+            tinted: HashMap::new(),
+            level_pack: String::from(DEFAULT_LEVEL_PACK),
+            watcher,
         })
     }
 
-    pub fn font(&self) -> &Font {
-        &self.font
+    pub fn atlas(&self) -> &Texture<'_> {
+        self.atlas.texture()
     }
 
-    pub fn texture_info<'a, T: Hash + Eq + std::fmt::Debug>(
-        map: &'a HashMap<T, TextureInfo>,
-        key: &T,
-    ) -> &'a TextureInfo {
-        map.get(key)
-            .or_else(|| {
-                panic_with_messagebox!(
-                    "Failed to find texture info for {:?}",
-                    key
-                )
-            })
-            .unwrap()
+    pub fn layer_chunk(
+        &self,
+        level: usize,
+        chunk: usize,
+    ) -> Option<&Texture<'_>> {
+        self.layer_cache.get(&(level, chunk))
+    }
+
+    pub fn thumbnail(&self, level: &LevelFile) -> Option<&Texture<'_>> {
+        self.thumbnails
+            .get(&level.name)
+            .filter(|(modified, _)| *modified == level.modified)
+            .map(|(_, texture)| texture)
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
     }
 
+    // Elements missing from the texture info are drawn with the
+    // placeholder texture.
     pub fn block_texture_info(&self, block: BlockType) -> &TextureInfo {
-        Self::texture_info(&self.texture_info.blocks, &block)
+        self.texture_info.blocks[block as usize]
+            .as_ref()
+            .unwrap_or(&self.texture_info.placeholder)
     }
 
     pub fn entity_texture_info(&self, entity: TextureId) -> &TextureInfo {
-        Self::texture_info(&self.texture_info.entities, &entity)
+        self.texture_info
+            .entities
+            .get(&entity)
+            .unwrap_or(&self.texture_info.placeholder)
+    }
+
+    // Clips missing in `info.json` loop the animation of the sprite's
+    // default texture.
+    pub fn animation_clip(&self, sprite: Sprite, clip: Clip) -> AnimationClip {
+        let clips = self.texture_info.animations.get(&sprite);
+        match clips.and_then(|clips| clips.get(&clip)) {
+            Some(clip) => *clip,
+            None => {
+                let texture = sprite.default_texture();
+                let animation = &self.entity_texture_info(texture).animation;
+                let mut fallback = AnimationClip::looping(
+                    texture,
+                    animation.frames,
+                    animation.speed,
+                );
+                // dying entities have to be removed at some point
+                fallback.looping = clip != Clip::Death;
+                fallback
+            },
+        }
+    }
+
+    pub fn emitter_info(&self, emitter: Emitter) -> Option<&EmitterInfo> {
+        self.texture_info.emitters.get(&emitter)
+    }
+
+    pub fn parallax_layers(&self, theme: LevelTheme) -> Rc<[ParallaxLayer]> {
+        self.texture_info
+            .parallax
+            .get(&theme)
+            .cloned()
+            .unwrap_or_else(|| Rc::new([]))
     }
 
     pub fn bg_texture_info(&self, bg: BackgroundElement) -> &TextureInfo {
-        Self::texture_info(&self.texture_info.background, &bg)
+        self.texture_info.background[bg as usize]
+            .as_ref()
+            .unwrap_or(&self.texture_info.placeholder)
+    }
+
+    pub fn save_level(
+        &mut self,
+        name: &str,
+        level: &Level,
+    ) -> ResourceResult<()> {
+        self.thumbnails.remove(name);
+        let path = self.saved_path("levels", &level_file(name))?;
+        write_level(&path, level)
+    }
+
+    pub fn save_settings(&self, settings: &Settings) -> Result<()> {
+        settings.save(&self.paths.user.join(SETTINGS_FILE))
+    }
+
+    pub fn load_level(&self, name: &str) -> ResourceResult<Level> {
+        read_level(&level_path(&self.paths, name))
+    }
+
+    pub fn level_exists(&self, name: &str) -> bool {
+        level_path(&self.paths, name).exists()
     }
 
-    pub fn texture(&mut self, name: &str) -> Rc<Texture> {
-        self.res_path
-            .join(format!("textures/{}.png", name))
-            .to_str()
-            .and_then(|path_str| self.textures.load(path_str).ok())
-            .unwrap_or_else(|| {
-                panic_with_messagebox!("Failed to load texture {}", name)
+    // Saved files are written to the user directory, which is created when
+    // it's missing.
+    fn saved_path(&self, dir: &str, file: &str) -> ResourceResult<PathBuf> {
+        self.paths
+            .saved_file(dir, file)
+            .map_err(|source| ResourceError::Write {
+                path: self.paths.user.join(dir),
+                source,
             })
     }
 
-    pub fn save_level(&self, name: &str, level: &Level) {
-        let serialized = serde_json::to_string(&LevelJSON::from(level))
-            .unwrap_or_else(|err| {
-                panic_with_messagebox!(
-                    "Failed to serialize a level ({})!",
-                    err
-                );
-            });
-        let path = self.res_path.join("levels/").join(format!("{}.lvl", name));
-        fs::write(path, serialized).unwrap_or_else(|err| {
-            panic_with_messagebox!(
-                "Failed to write to file '{}' ({})!",
-                name,
-                err
-            )
+    // Newest levels come first.
+    pub fn list_levels(&self) -> Vec<LevelFile> {
+        let files = list_files(&self.paths.file_dirs("levels"), "lvl");
+        let mut levels: Vec<LevelFile> = files
+            .into_iter()
+            .map(|(name, path)| {
+                let modified = fs::metadata(&path)
+                    .and_then(|meta| meta.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                LevelFile { name, modified }
+            })
+            .collect();
+
+        levels.sort_by(|a, b| {
+            b.modified.cmp(&a.modified).then_with(|| a.name.cmp(&b.name))
         });
+        levels
     }
 
-    pub fn load_level(&self, name: &str) -> Option<Level> {
-        let path = self.res_path.join("levels/").join(format!("{}.lvl", name));
+    pub fn duplicate_level(&self, name: &str, copy: &str) -> Result<()> {
+        let path = level_path(&self.paths, name);
+        fs::copy(path, self.saved_path("levels", &level_file(copy))?)?;
+        Ok(())
+    }
 
-        if !path.exists() {
-            return None;
+    // Only levels made by the player can be renamed or deleted, the ones
+    // shipped with the game would come back from the resource directory,
+    // which may be read-only anyway.
+    fn own_level(&self, name: &str) -> Result<PathBuf> {
+        let file = level_file(name);
+        let shipped = self.paths.resources.join("levels").join(&file);
+        if self.paths.user != self.paths.resources && shipped.exists() {
+            let message = "LEVELS OF THE GAME CAN'T BE RENAMED OR DELETED";
+            return Err(message.into());
         }
+        Ok(self.paths.user.join("levels").join(file))
+    }
 
-        fs::read_to_string(path)
-            .map_err(|err| err.to_string())
-            .and_then(|contents| {
-                serde_json::from_str::<LevelJSON>(&contents)
-                    .map_err(|err| err.to_string())
-            })
-            .map_err(|err| {
-                panic_with_messagebox!(
-                    "Failed to load level '{}' due to an error in JSON file: \
-                     {}\n",
-                    name,
-                    err
-                )
-            })
-            .map(|lvl| lvl.into())
-            .ok()
+    // Renamed levels keep their place in the level pack.
+    pub fn rename_level(&mut self, name: &str, new_name: &str) -> Result<()> {
+        let path = self.own_level(name)?;
+        let new_path = self.saved_path("levels", &level_file(new_name))?;
+        fs::rename(path, new_path)?;
+        self.thumbnails.remove(name);
+
+        let mut list = self.load_level_list()?;
+        if list.iter().any(|level| level == name) {
+            for level in list.iter_mut().filter(|level| *level == name) {
+                *level = String::from(new_name);
+            }
+            self.save_level_list(&list)?;
+        }
+        Ok(())
     }
 
-    pub fn load_existing_level(&self, name: &str) -> Level {
-        self.load_level(name).unwrap_or_else(|| {
-            panic_with_messagebox!("Level '{}' does not exist!", name)
-        })
+    pub fn delete_level(&mut self, name: &str) -> Result<()> {
+        fs::remove_file(self.own_level(name)?)?;
+        self.thumbnails.remove(name);
+
+        let mut list = self.load_level_list()?;
+        if list.iter().any(|level| level == name) {
+            list.retain(|level| level != name);
+            self.save_level_list(&list)?;
+        }
+        Ok(())
+    }
+
+    // Stamps that fail to load are left out, so that a single broken file
+    // doesn't make the editor unusable.
+    pub fn load_stamps(&self) -> Vec<(String, Stamp)> {
+        let files = list_files(&self.paths.file_dirs("stamps"), "json");
+        let mut stamps: Vec<(String, Stamp)> = files
+            .into_iter()
+            .filter_map(|(name, path)| Some((name, read_stamp(&path).ok()?)))
+            .collect();
+
+        stamps.sort_by(|(a, _), (b, _)| a.cmp(b));
+        stamps
+    }
+
+    pub fn save_stamp(&self, name: &str, stamp: &Stamp) -> Result<()> {
+        validate_name(name)?;
+        let path = self.saved_path("stamps", &format!("{}.json", name))?;
+        let serialized = serde_json::to_string_pretty(stamp)?;
+        write_file_atomic(&path, &serialized)?;
+        Ok(())
+    }
+
+    pub fn set_level_pack(&mut self, pack: &str) {
+        self.level_pack = String::from(pack);
+    }
+
+    pub fn level_pack(&self) -> &str {
+        &self.level_pack
+    }
+
+    pub fn load_level_list(&self) -> ResourceResult<Vec<String>> {
+        read_level_list(&self.paths, &self.level_pack)
+    }
+
+    pub fn save_level_list(&self, levels: &[String]) -> ResourceResult<()> {
+        let path = self.saved_path("levels", &pack_file(&self.level_pack))?;
+        let list = LevelList {
+            levels: levels.to_vec(),
+        };
+        write_json(&path, &list, true)
     }
 
-    pub fn load_level_list(&self) -> Vec<String> {
-        #[derive(Deserialize, Serialize)]
-        struct LevelList {
-            levels: Vec<String>,
+    // A broken recovery file is treated as if there was none.
+    pub fn load_recovery(&self) -> Option<Recovery> {
+        let path = self.paths.user.join(RECOVERY_FILE);
+        let contents = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn save_recovery(&self, recovery: &Recovery) -> Result<()> {
+        let serialized = serde_json::to_string(recovery)?;
+        write_file_atomic(&self.paths.user.join(RECOVERY_FILE), &serialized)?;
+        Ok(())
+    }
+
+    pub fn discard_recovery(&self) {
+        fs::remove_file(self.paths.user.join(RECOVERY_FILE)).ok();
+    }
+}
+
+pub const DEFAULT_LEVEL_PACK: &str = "levels";
+
+const RECOVERY_FILE: &str = "recovery.json";
+
+// Unsaved changes of the level open in the editor. They are written
+// periodically and offered for restoring when the game is started again
+// after a crash.
+#[derive(Deserialize, Serialize)]
+pub struct Recovery {
+    pub level_name: String,
+    level:          LevelJSON,
+}
+
+impl Recovery {
+    pub fn new(level_name: &str, level: &Level) -> Recovery {
+        Recovery {
+            level_name: String::from(level_name),
+            level:      LevelJSON::from(level),
         }
+    }
 
-        let list_path = self.res_path.join("levels/");
-        let list: LevelList = fs::read_to_string(list_path.join("levels.json"))
-            .map_err(|err| err.to_string())
-            .and_then(|contents| {
-                serde_json::from_str(&contents).map_err(|err| err.to_string())
-            })
-            .unwrap_or_else(|err| {
-                panic_with_messagebox!(
-                    "Failed to load level list due to an error in JSON \
-                     file:\n {}",
-                    err
-                )
-            });
-        list.levels
+    pub fn into_level(self) -> ResourceResult<Level> {
+        self.level.try_into()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct LevelList {
+    levels: Vec<String>,
+}
+
+// Names end up as file names and in the level pack, so they are limited to
+// characters that are safe on every platform.
+pub fn validate_name(name: &str) -> std::result::Result<(), &'static str> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || "-_ ".contains(c);
+    if name.is_empty() {
+        Err("THE NAME CAN'T BE EMPTY")
+    } else if !name.chars().all(valid) {
+        Err("USE ONLY LETTERS, DIGITS, SPACES, - AND _")
+    } else {
+        Ok(())
     }
 }
 
+// Levels saved by the player override the shipped ones with the same name.
+pub fn level_path(paths: &GamePaths, name: &str) -> PathBuf {
+    paths.find_file("levels", &level_file(name))
+}
+
+fn level_file(name: &str) -> String {
+    format!("{}.lvl", name)
+}
+
+fn pack_file(pack: &str) -> String {
+    format!("{}.json", pack)
+}
+
+// Files with the given extension by their names, later directories
+// override the files of the earlier ones.
+fn list_files(dirs: &[PathBuf], extension: &str) -> HashMap<String, PathBuf> {
+    let mut files = HashMap::new();
+    for dir in dirs {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for path in entries.filter_map(|entry| entry.ok()).map(|e| e.path()) {
+            if path.extension() != Some(extension.as_ref()) {
+                continue;
+            }
+            if let Some(name) = path.file_stem() {
+                files.insert(name.to_string_lossy().into_owned(), path);
+            }
+        }
+    }
+    files
+}
+
+// Level files can be read without the SDL context, which lets the command
+// line tools work on them.
+pub fn read_level(path: &Path) -> ResourceResult<Level> {
+    let level: LevelJSON = read_json(path)?;
+    level.try_into()
+}
+
+pub fn read_stamp(path: &Path) -> Result<Stamp> {
+    let stamp: Stamp = serde_json::from_str(&fs::read_to_string(path)?)?;
+    if !stamp.is_valid() {
+        return Err(format!("Stamp {} is corrupted!", path.display()).into());
+    }
+    Ok(stamp)
+}
+
+// A level pack is a list of level names stored in the levels directory.
+pub fn read_level_list(
+    paths: &GamePaths,
+    pack: &str,
+) -> ResourceResult<Vec<String>> {
+    let path = paths.find_file("levels", &pack_file(pack));
+    let list: LevelList = read_json(&path)?;
+    Ok(list.levels)
+}
+
+// The game writes levels as compact JSON, files with the .json extension
+// are pretty printed so that they can be edited and diffed by hand.
+pub fn write_level(path: &Path, level: &Level) -> ResourceResult<()> {
+    let pretty = path.extension().is_some_and(|ext| ext == "json");
+    write_json(path, &LevelJSON::from(level), pretty)
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> ResourceResult<T> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| ResourceError::io(path, err))?;
+    serde_json::from_str(&contents)
+        .map_err(|err| ResourceError::json(path, err))
+}
+
+fn write_json<T: Serialize>(
+    path: &Path,
+    value: &T,
+    pretty: bool,
+) -> ResourceResult<()> {
+    let serialized = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    let serialized = serialized.map_err(|source| ResourceError::Serialize {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    write_file_atomic(path, &serialized).map_err(|source| {
+        ResourceError::Write {
+            path: path.to_path_buf(),
+            source,
+        }
+    })
+}
+
+impl ResourceChanges {
+    pub fn is_empty(&self) -> bool {
+        !self.textures && self.levels.is_empty() && !self.forced
+    }
+
+    pub fn level_changed(&self, name: &str) -> bool {
+        self.forced || self.levels.iter().any(|level| level == name)
+    }
+}
+
+fn classify_changes(paths: &[PathBuf]) -> ResourceChanges {
+    let mut changes = ResourceChanges::default();
+    for path in paths {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => changes.textures = true,
+            Some("json") if path.ends_with("textures/info.json") => {
+                changes.textures = true;
+            },
+            Some("lvl") => {
+                if let Some(name) = path.file_stem() {
+                    changes.levels.push(name.to_string_lossy().into_owned());
+                }
+            },
+            _ => (),
+        }
+    }
+    changes
+}
+
+fn load_textures<'a>(
+    loader: &'a TextureFactory,
+    res_path: &Path,
+) -> Result<(TextureTable, TextureAtlas<'a>)> {
+    let texture_paths: TexturePaths =
+        read_json(&res_path.join("textures/info.json"))?;
+
+    let mut texture_info = TextureTable::from(texture_paths);
+    let mut paths: Vec<String> = texture_info
+        .infos_mut()
+        .map(|info| info.path.clone())
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+    let textures_path = res_path.join("textures");
+    let atlas = TextureAtlas::new(loader, &textures_path, &paths)?;
+
+    for info in texture_info.infos_mut() {
+        info.atlas_origin = atlas.origin(&info.path).unwrap_or_default();
+    }
+    Ok((texture_info, atlas))
+}
+
 impl TextureInfo {
     pub fn frame_index(&self, tick: u32) -> u32 {
         let frames = self.animation.frames;
@@ -275,6 +856,16 @@ impl TextureInfo {
     pub fn hitbox_offset(&self) -> (i32, i32) {
         self.hitbox_offset
     }
+
+    pub fn is_animated(&self) -> bool {
+        self.animation.frames > 1
+    }
+
+    // Translates a region of this texture to the texture atlas.
+    pub fn atlas_region(&self, region: Rect) -> Rect {
+        let (x, y) = self.atlas_origin;
+        rect!(region.x() + x, region.y() + y, region.width(), region.height())
+    }
 }
 
 // for serde_json default values purposes
@@ -0,0 +1,63 @@
+use crate::render::*;
+use crate::utility::*;
+
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::path::Path;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+pub const WINDOW_SIZES: [(u32, u32); 4] =
+    [(960, 540), (1280, 720), (1600, 900), (1920, 1080)];
+
+#[derive(Deserialize, Serialize)]
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+pub struct Settings {
+    #[serde(default = "default_window_size")]
+    pub window_size: (u32, u32),
+    #[serde(default)]
+    pub fullscreen:  bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            window_size: default_window_size(),
+            fullscreen:  false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Settings {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let serialized = serde_json::to_string_pretty(self)?;
+        write_file_atomic(path, &serialized)?;
+        Ok(())
+    }
+
+    pub fn next_window_size(&mut self) {
+        let next = WINDOW_SIZES
+            .iter()
+            .position(|&size| size == self.window_size)
+            .map_or(0, |i| (i + 1) % WINDOW_SIZES.len());
+        self.window_size = WINDOW_SIZES[next];
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        self.fullscreen ^= true;
+    }
+}
+
+// for serde_json default values purposes
+fn default_window_size() -> (u32, u32) {
+    (LOGICAL_WIDTH, LOGICAL_HEIGHT)
+}
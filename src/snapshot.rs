@@ -0,0 +1,90 @@
+use crate::block::*;
+use crate::level::*;
+use crate::parallax::*;
+use crate::paths::*;
+use crate::render::*;
+use crate::resource::*;
+use crate::utility::*;
+
+use sdl2::image::SaveSurface;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::surface::Surface;
+use sdl2::ttf::Sdl2TtfContext;
+
+use std::path::Path;
+
+pub const LEVEL_PIXEL_WIDTH: u32 = LEVEL_WIDTH as u32 * BLOCK_SIZE;
+pub const LEVEL_PIXEL_HEIGHT: u32 = LEVEL_HEIGHT as u32 * BLOCK_SIZE;
+
+// Renders a whole level as it looks in the editor, including the entities
+// placed in it.
+pub fn snapshot_level(
+    level: &Level,
+    ttf: &Sdl2TtfContext,
+    paths: &GamePaths,
+    scale: f64,
+) -> Result<Surface<'static>> {
+    render_offscreen(ttf, paths, scale, |renderer, camera, res| {
+        renderer.clear(Color::from(level.theme));
+        let parallax = ThemedParallax { theme: level.theme };
+        renderer.draw(&parallax).camera(camera).show(res);
+        renderer.draw(level).camera(camera).show(res);
+    })
+}
+
+// Renders the current state of a level that is being played.
+pub fn snapshot_playable_level(
+    level: &PlayableLevel,
+    ttf: &Sdl2TtfContext,
+    paths: &GamePaths,
+    scale: f64,
+) -> Result<Surface<'static>> {
+    render_offscreen(ttf, paths, scale, |renderer, camera, res| {
+        renderer.clear(Color::from(level.prototype.theme));
+        renderer
+            .draw(level)
+            .mode(DrawMode::Game)
+            .camera(camera)
+            .show(res);
+    })
+}
+
+pub fn save_png(surface: &Surface, path: &Path) -> Result<()> {
+    surface.save(path)?;
+    Ok(())
+}
+
+// The offscreen renderer has its own texture creator, so it needs its own
+// set of resources as well.
+fn render_offscreen<F>(
+    ttf: &Sdl2TtfContext,
+    paths: &GamePaths,
+    scale: f64,
+    draw: F,
+) -> Result<Surface<'static>>
+where
+    F: FnOnce(&mut Renderer, Camera, &mut ResourceManager),
+{
+    let (width, height) = (LEVEL_PIXEL_WIDTH, LEVEL_PIXEL_HEIGHT);
+    let mut renderer = Renderer::offscreen(width, height)?;
+    let creator = renderer.canvas.texture_creator();
+
+    {
+        let cache = TextureCache::new(&creator);
+        let mut res = ResourceManager::new(cache, ttf, paths.clone())?;
+        let camera = Camera::new(0, 0).view(width, height);
+        draw(&mut renderer, camera, &mut res);
+    }
+
+    let surface = renderer.into_surface()?;
+    if (scale - 1.0).abs() < f64::EPSILON {
+        return Ok(surface);
+    }
+
+    let scaled_width = ((f64::from(width) * scale) as u32).max(1);
+    let scaled_height = ((f64::from(height) * scale) as u32).max(1);
+    let mut scaled =
+        Surface::new(scaled_width, scaled_height, PixelFormatEnum::RGBA8888)?;
+    surface.blit_scaled(None, &mut scaled, None)?;
+    Ok(scaled)
+}
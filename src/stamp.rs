@@ -0,0 +1,263 @@
+use crate::background::*;
+use crate::block::*;
+use crate::entity::*;
+use crate::hitbox::*;
+use crate::level::*;
+use crate::render::*;
+use crate::resource::*;
+use crate::utility::*;
+
+use sdl2::pixels::Color;
+
+use serde::{Deserialize, Serialize};
+
+// Rectangle of blocks, in block coordinates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x:      usize,
+    pub y:      usize,
+    pub width:  usize,
+    pub height: usize,
+}
+
+// Piece of a level covering all of its layers. Entity positions are
+// relative to the top left corner of the stamp.
+#[derive(Clone, Debug)]
+#[derive(Deserialize, Serialize)]
+pub struct Stamp {
+    width:      usize,
+    height:     usize,
+    blocks:     Vec<Block>,
+    background: Vec<BackgroundElement>,
+    entities:   Vec<EntityPrototype>,
+}
+
+pub struct ThemedStamp<'a> {
+    pub stamp: &'a Stamp,
+    pub theme: LevelTheme,
+}
+
+impl Region {
+    pub fn from_corners(a: (usize, usize), b: (usize, usize)) -> Region {
+        Region {
+            x:      a.0.min(b.0),
+            y:      a.1.min(b.1),
+            width:  a.0.max(b.0) - a.0.min(b.0) + 1,
+            height: a.1.max(b.1) - a.1.min(b.1) + 1,
+        }
+    }
+
+    pub fn contains(self, (x, y): (usize, usize)) -> bool {
+        (self.x..self.x + self.width).contains(&x)
+            && (self.y..self.y + self.height).contains(&y)
+    }
+
+    pub fn hitbox(self) -> Hitbox {
+        rect!(
+            self.x as u32 * BLOCK_SIZE,
+            self.y as u32 * BLOCK_SIZE,
+            self.width as u32 * BLOCK_SIZE,
+            self.height as u32 * BLOCK_SIZE
+        )
+    }
+
+    fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        let cols = self.x..self.x + self.width;
+        (self.y..self.y + self.height)
+            .flat_map(move |y| cols.clone().map(move |x| (x, y)))
+    }
+}
+
+impl Stamp {
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let cells = self.width * self.height;
+        cells > 0
+            && self.blocks.len() == cells
+            && self.background.len() == cells
+    }
+
+    fn index(&self, (x, y): (usize, usize)) -> usize {
+        y * self.width + x
+    }
+
+    // Mirrors the stamp, including the blocks and elements that have
+    // a left and a right variant. Elements wider than a block keep their
+    // origin in the top left corner, so they have to be shifted.
+    pub fn flipped(&self, res: &ResourceManager) -> Stamp {
+        let mut flipped = self.clone();
+        let pixel_width = (self.width as u32 * BLOCK_SIZE) as i32;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mirrored_x = self.width - 1 - x;
+                let mut block = self.blocks[self.index((x, y))];
+                block.set_kind(block.kind().mirrored());
+                let index = flipped.index((mirrored_x, y));
+                flipped.blocks[index] = block;
+                flipped.background[index] = BackgroundElement::Air;
+            }
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let bg = self.background[self.index((x, y))];
+                if !bg.is_visible() {
+                    continue;
+                }
+
+                let bg_width = res.bg_texture_info(bg).width;
+                let span = bg_width.div_ceil(BLOCK_SIZE) as usize;
+                let mirrored_x = (self.width - x).saturating_sub(span);
+                let index = flipped.index((mirrored_x, y));
+                flipped.background[index] = bg.mirrored();
+            }
+        }
+
+        for entity in flipped.entities.iter_mut() {
+            let width = entity.hitbox().width() as i32;
+            entity.position.0 = pixel_width - entity.position.0 - width;
+            entity.properties.direction = match entity.properties.direction {
+                XDirection::Left => XDirection::Right,
+                XDirection::Right => XDirection::Left,
+                XDirection::Still => XDirection::Still,
+            };
+        }
+
+        flipped
+    }
+}
+
+impl Level {
+    // End flags and spawn points aren't copied, a level needs exactly one
+    // of each.
+    pub fn copy_region(&self, region: Region) -> Stamp {
+        let blocks = region.cells().map(|pos| self.get_block(pos)).collect();
+        let background = region.cells().map(|pos| self.get_bg(pos)).collect();
+
+        let area = region.hitbox();
+        let (origin_x, origin_y) = (area.x(), area.y());
+        let entities = self
+            .entities()
+            .iter()
+            .filter(|entity| area.collides(&entity.position))
+            .filter(|entity| !entity.is_unique())
+            .map(|entity| {
+                let (x, y) = entity.position;
+                EntityPrototype {
+                    position: (x - origin_x, y - origin_y),
+                    ..*entity
+                }
+            })
+            .collect();
+
+        Stamp {
+            width: region.width,
+            height: region.height,
+            blocks,
+            background,
+            entities,
+        }
+    }
+
+    pub fn clear_region(&mut self, region: Region) {
+        for pos in region.cells() {
+            self.set_block(pos, Block::default());
+            self.set_bg(pos, BackgroundElement::default());
+        }
+
+        let area = region.hitbox();
+        self.retain_entities(|entity| {
+            !area.collides(&entity.position) || entity.is_unique()
+        });
+    }
+
+    // Only the non-empty parts of the stamp overwrite the level, so stamps
+    // of irregular shapes can be placed over existing terrain. The parts
+    // that don't fit into the level are cut off.
+    pub fn paste(&mut self, stamp: &Stamp, (x, y): (usize, usize)) {
+        for stamp_y in 0..stamp.height {
+            for stamp_x in 0..stamp.width {
+                let pos = (x + stamp_x, y + stamp_y);
+                if pos.0 >= LEVEL_WIDTH || pos.1 >= LEVEL_HEIGHT {
+                    continue;
+                }
+
+                let index = stamp.index((stamp_x, stamp_y));
+                let block = stamp.blocks[index];
+                if block.is_visible() {
+                    self.set_block(pos, block);
+                }
+
+                let bg = stamp.background[index];
+                if bg.is_visible() {
+                    self.set_bg(pos, bg);
+                }
+            }
+        }
+
+        // The spawn and the end flag are never replaced or duplicated, even
+        // by stamp files edited by hand.
+        let origin_x = (x as u32 * BLOCK_SIZE) as i32;
+        let origin_y = (y as u32 * BLOCK_SIZE) as i32;
+        for entity in stamp.entities.iter().filter(|e| !e.is_unique()) {
+            let (entity_x, entity_y) = entity.position;
+            let position = (entity_x + origin_x, entity_y + origin_y);
+            self.retain_entities(|other| {
+                other.position != position || other.is_unique()
+            });
+            self.insert_entity(EntityPrototype { position, ..*entity });
+        }
+    }
+}
+
+impl Drawable for ThemedStamp<'_> {
+    fn show(data: DrawCall<Self>, res: &mut ResourceManager) {
+        let stamp = data.object.stamp;
+        let theme = data.object.theme;
+        let (origin_x, origin_y) = data.position;
+
+        for y in 0..stamp.height {
+            for x in 0..stamp.width {
+                let pos = (
+                    origin_x + (x as u32 * BLOCK_SIZE) as i32,
+                    origin_y + (y as u32 * BLOCK_SIZE) as i32,
+                );
+                let index = stamp.index((x, y));
+
+                let element = stamp.background[index];
+                let bg = ThemedBackgroundElement { element, theme };
+                pass_draw!(data, &bg).position(pos).show(res);
+
+                let block = stamp.blocks[index];
+                let block = ThemedBlock { block, theme };
+                pass_draw!(data, &block).position(pos).show(res);
+            }
+        }
+
+        for entity in stamp.entities.iter() {
+            let (x, y) = entity.position;
+            let entity = EntityPrototype {
+                position: (x + origin_x, y + origin_y),
+                ..*entity
+            };
+            pass_draw!(data, &entity).show(res);
+        }
+
+        let mut outline = rect!(
+            origin_x,
+            origin_y,
+            stamp.width as u32 * BLOCK_SIZE,
+            stamp.height as u32 * BLOCK_SIZE
+        );
+        data.camera.move_rect(&mut outline);
+        data.renderer.canvas.set_draw_color(Color::RGB(255, 255, 0));
+        data.renderer
+            .canvas
+            .draw_rect(outline)
+            .expect("Failed to draw the outline of a stamp!");
+    }
+}
@@ -1,18 +1,27 @@
+use crate::audio::*;
+use crate::browser::*;
+use crate::cli::*;
 use crate::controller::*;
 use crate::editor::*;
+use crate::error::*;
 use crate::game::*;
 use crate::menu::*;
 use crate::render::*;
 use crate::resource::*;
+use crate::settings::*;
 use crate::utility::*;
 
 use sdl2::keyboard::{Keycode, TextInputUtil};
-use sdl2::pixels::Color;
+use sdl2::sys::SDL_EventType;
 use sdl2::Sdl;
 use sdl2::{event::Event, EventPump};
 
 use std::mem::replace;
 
+const RENDER_TARGETS_RESET: u32 =
+    SDL_EventType::SDL_RENDER_TARGETS_RESET as u32;
+const RENDER_DEVICE_RESET: u32 = SDL_EventType::SDL_RENDER_DEVICE_RESET as u32;
+
 pub struct GameState<'a> {
     activity:   Activity,
     event_pump: EventPump,
@@ -20,11 +29,14 @@ pub struct GameState<'a> {
 }
 
 pub struct SharedState<'a> {
-    pub should_exit: bool,
-    pub controller:  Controller,
-    pub resources:   ResourceManager<'a>,
-    pub text_input:  TextInput<'a>,
-    pub frame:       u32,
+    pub should_exit:      bool,
+    pub controller:       Controller,
+    pub resources:        ResourceManager<'a>,
+    pub text_input:       TextInput<'a>,
+    pub frame:            u32,
+    pub settings:         Settings,
+    pub settings_changed: bool,
+    pub jukebox:          Jukebox,
 }
 
 pub struct TextInput<'a> {
@@ -34,9 +46,12 @@ pub struct TextInput<'a> {
 
 pub enum Activity {
     Game(Box<Game>),
-    Editor(Editor),
-    FileInputScreen,
+    Editor(Box<Editor>),
+    LevelBrowser(Box<LevelBrowser>),
     MainMenu(MainMenu),
+    Settings(SettingsMenu),
+    Recovery(RecoveryMenu),
+    Error(ErrorScreen),
 }
 
 #[derive(PartialEq, Eq)]
@@ -90,30 +105,89 @@ impl TextInput<'_> {
 }
 
 impl Activity {
-    pub fn new_game(resources: &ResourceManager) -> Activity {
-        Activity::Game(Box::new(Game::new(resources)))
+    pub fn new_game(
+        resources: &ResourceManager,
+        level: Option<&str>,
+    ) -> Activity {
+        match Game::new(resources, level) {
+            Ok(game) => Activity::Game(Box::new(game)),
+            Err(err) => Activity::new_error_screen(&err),
+        }
+    }
+
+    pub fn from_launch(
+        resources: &ResourceManager,
+        launch: Launch,
+    ) -> Activity {
+        match launch {
+            Launch::MainMenu => Activity::new_main_menu_or_recovery(resources),
+            Launch::Game(level) => {
+                Activity::new_game(resources, level.as_deref())
+            },
+            Launch::Editor(name) => Activity::new_editor(resources, &name),
+        }
     }
 
     pub fn new_editor(resources: &ResourceManager, name: &str) -> Activity {
-        Activity::Editor(Editor::new(resources, name))
+        match Editor::new(resources, name) {
+            Ok(editor) => Activity::Editor(Box::new(editor)),
+            Err(err) => Activity::new_error_screen(&err),
+        }
+    }
+
+    pub fn new_level_browser(resources: &ResourceManager) -> Activity {
+        Activity::LevelBrowser(Box::new(LevelBrowser::new(resources)))
+    }
+
+    pub fn new_recovered_editor(
+        resources: &ResourceManager,
+        recovery: Recovery,
+    ) -> Activity {
+        match Editor::recover(resources, recovery) {
+            Ok(editor) => Activity::Editor(Box::new(editor)),
+            Err(err) => Activity::new_error_screen(&err),
+        }
+    }
+
+    pub fn new_error_screen(error: &ResourceError) -> Activity {
+        Activity::Error(ErrorScreen::new(error))
+    }
+
+    // Unsaved changes left by a crash are offered before the main menu.
+    pub fn new_main_menu_or_recovery(resources: &ResourceManager) -> Activity {
+        match resources.load_recovery() {
+            Some(recovery) => {
+                Activity::Recovery(RecoveryMenu::new(recovery))
+            },
+            None => Activity::new_main_menu(resources),
+        }
     }
 
     pub fn new_main_menu(resources: &ResourceManager) -> Activity {
         Activity::MainMenu(MainMenu::new(resources))
     }
+
+    pub fn new_settings_menu(settings: &Settings) -> Activity {
+        Activity::Settings(SettingsMenu::new(settings))
+    }
 }
 
 impl SharedState<'_> {
     pub fn new<'a>(
         resources: ResourceManager<'a>,
         text_input: TextInput<'a>,
+        settings: Settings,
     ) -> SharedState<'a> {
+        let music_path = resources.paths().resources.join("music");
         SharedState {
+            jukebox: Jukebox::new(&music_path),
             should_exit: false,
             controller: Controller::new(),
             resources,
             frame: 0,
             text_input,
+            settings,
+            settings_changed: false,
         }
     }
 }
@@ -123,10 +197,12 @@ impl GameState<'_> {
         resources: ResourceManager<'a>,
         context: &Sdl,
         text_input: TextInput<'a>,
+        settings: Settings,
+        launch: Launch,
     ) -> Result<GameState<'a>> {
         let event_pump = context.event_pump()?;
-        let activity = Activity::new_main_menu(&resources);
-        let shared_state = SharedState::new(resources, text_input);
+        let activity = Activity::from_launch(&resources, launch);
+        let shared_state = SharedState::new(resources, text_input, settings);
 
         Ok(GameState {
             event_pump,
@@ -151,13 +227,10 @@ impl GameState<'_> {
         self.state.should_exit
     }
 
+    // The tick never resets, so that looping animations don't jump when it
+    // would wrap around.
     pub fn update_tick(&mut self) {
-        const SECONDS_UNTIL_RESET : u32 = 10;
-        if self.state.frame >= FPS * SECONDS_UNTIL_RESET {
-            self.state.frame = 0;
-        } else {
-            self.state.frame += 1;
-        }
+        self.state.frame = self.state.frame.wrapping_add(1);
     }
 
     pub fn update(&mut self) {
@@ -167,13 +240,56 @@ impl GameState<'_> {
         self.state.controller.update(&events);
         self.process_events(&events);
 
+        if self.state.controller.was_key_pressed(Key::Fullscreen) {
+            self.state.settings.toggle_fullscreen();
+            self.state.settings_changed = true;
+        }
+
         if self.should_exit() {
             return;
         }
 
+        self.reload_resources();
         self.update_activity();
     }
 
+    // Files changed by other programs are picked up automatically, `F5`
+    // reloads everything right away.
+    fn reload_resources(&mut self) {
+        let res = &mut self.state.resources;
+        let changes = if self.state.controller.was_key_pressed(Key::Reload) {
+            res.reload_all()
+        } else {
+            res.poll_changes()
+        };
+        if changes.is_empty() {
+            return;
+        }
+
+        let res = &self.state.resources;
+        match &mut self.activity {
+            Activity::Game(game) => game.reload(&changes, res),
+            Activity::Editor(editor) => editor.reload(&changes, res),
+            Activity::LevelBrowser(browser) => browser.reload(res),
+            _ => (),
+        }
+    }
+
+    pub fn update_window(&mut self, renderer: &mut Renderer) -> Result<()> {
+        if self.state.settings_changed {
+            self.state.settings_changed = false;
+            renderer.apply_settings(&self.state.settings)?;
+            // The settings still apply to this session when they can't be
+            // saved.
+            if let Err(err) =
+                self.state.resources.save_settings(&self.state.settings)
+            {
+                eprintln!("Failed to save the settings: {}", err);
+            }
+        }
+        Ok(())
+    }
+
     fn process_events(&mut self, events: &[Event]) {
         for event in events.iter() {
             match event {
@@ -192,6 +308,20 @@ impl GameState<'_> {
                         self.state.text_input.backspace();
                     }
                 },
+                // Some renderers lose the contents of render targets, e.g.
+                // Direct3D when the window is resized or goes fullscreen.
+                // These events are too new for the SDL bindings.
+                Event::Unknown { type_, .. }
+                    if *type_ == RENDER_TARGETS_RESET
+                        || *type_ == RENDER_DEVICE_RESET =>
+                {
+                    let res = &mut self.state.resources;
+                    res.clear_render_targets();
+                    if let Activity::LevelBrowser(browser) = &mut self.activity
+                    {
+                        browser.reload(res);
+                    }
+                },
                 _ => (),
             }
         }
@@ -201,28 +331,17 @@ impl GameState<'_> {
         match &mut self.activity {
             Activity::Game(game) => {
                 if game.update(&mut self.state).exited() {
+                    self.state.jukebox.stop();
                     replace(
                         &mut self.activity,
                         Activity::new_main_menu(&self.state.resources),
                     );
                 }
             },
-            activity @ Activity::FileInputScreen => {
-                let reading_text = self.state.text_input.is_active();
-                if !reading_text {
-                    self.state.text_input.start();
-                } else if self.state.controller.was_key_pressed(Key::Enter) {
-                    let file_name = self.state.text_input.end();
-                    replace(
-                        activity,
-                        Activity::new_editor(&self.state.resources, &file_name),
-                    );
-                } else if self.state.controller.was_key_pressed(Key::Escape) {
-                    self.state.text_input.end();
-                    replace(
-                        activity,
-                        Activity::new_main_menu(&self.state.resources),
-                    );
+            Activity::LevelBrowser(browser) => {
+                let activity = browser.update_and_get_activity(&mut self.state);
+                if let Some(activity) = activity {
+                    replace(&mut self.activity, activity);
                 }
             },
             Activity::Editor(editor) => {
@@ -239,11 +358,29 @@ impl GameState<'_> {
                     replace(&mut self.activity, activity);
                 }
             },
+            Activity::Settings(menu) => {
+                let activity = menu.update_and_get_activity(&mut self.state);
+                if let Some(activity) = activity {
+                    replace(&mut self.activity, activity);
+                }
+            },
+            Activity::Recovery(menu) => {
+                let activity = menu.update_and_get_activity(&mut self.state);
+                if let Some(activity) = activity {
+                    replace(&mut self.activity, activity);
+                }
+            },
+            Activity::Error(screen) => {
+                let activity = screen.update_and_get_activity(&mut self.state);
+                if let Some(activity) = activity {
+                    replace(&mut self.activity, activity);
+                }
+            },
         }
     }
 
     pub fn draw(&mut self, renderer: &mut Renderer) {
-        match &self.activity {
+        match &mut self.activity {
             Activity::Game(game) => {
                 game.draw(renderer, &mut self.state);
             },
@@ -253,28 +390,17 @@ impl GameState<'_> {
             Activity::MainMenu(menu) => {
                 menu.draw(renderer, &mut self.state);
             },
-            Activity::FileInputScreen => {
-                renderer.clear(Color::RGB(0, 0, 0));
-                let prompt = centered_text!("Level name: ");
-                let input = centered_text!(self.state.text_input.text());
-
-                renderer
-                    .draw(&prompt)
-                    .position((
-                        SCREEN_WIDTH as i32 / 2,
-                        SCREEN_HEIGHT as i32 / 2 - 100,
-                    ))
-                    .scale(0.2)
-                    .show(&mut self.state.resources);
-
-                renderer
-                    .draw(&input)
-                    .position((
-                        SCREEN_WIDTH as i32 / 2,
-                        SCREEN_HEIGHT as i32 / 2,
-                    ))
-                    .scale(0.25)
-                    .show(&mut self.state.resources);
+            Activity::Settings(menu) => {
+                menu.draw(renderer, &mut self.state);
+            },
+            Activity::Recovery(menu) => {
+                menu.draw(renderer, &mut self.state);
+            },
+            Activity::Error(screen) => {
+                screen.draw(renderer, &mut self.state);
+            },
+            Activity::LevelBrowser(browser) => {
+                browser.draw(renderer, &mut self.state);
             },
         }
 
@@ -1,20 +1,29 @@
 use serde::{Deserialize};
 
-#[derive(PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(PartialEq, Eq, Hash, Deserialize, Debug, Copy, Clone)]
 pub enum TextureId {
     CollectibleCoin,
     CollectibleMushroom,
     CollectibleStar,
     CollectibleFlower,
+    CollectibleOneUp,
+    CollectibleVine,
     PlayerStanding,
     PlayerRunning,
     PlayerJumping,
     BigPlayerStanding,
     BigPlayerRunning,
     BigPlayerJumping,
-    SmallCloud,
-    BigCloud,
     EnemyGoomba,
     EnemyKoopa,
+    EnemyBlooper,
+    EnemyCheepCheep,
+    EnemyFlyingKoopa,
+    EnemyPiranhaPlant,
+    EnemyShell,
+    EnemyBuzzyBeetle,
+    EnemySpiny,
+    EnemyHammerBro,
+    Hammer,
     Flag,
 }
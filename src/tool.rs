@@ -0,0 +1,160 @@
+use crate::level::*;
+
+use std::collections::VecDeque;
+
+pub const MAX_BRUSH_SIZE: usize = 9;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tool {
+    Pencil,
+    Fill,
+    Line,
+    Rectangle,
+    HollowRectangle,
+    Eraser,
+}
+
+impl Tool {
+    pub fn next(self) -> Tool {
+        match self {
+            Tool::Pencil => Tool::Fill,
+            Tool::Fill => Tool::Line,
+            Tool::Line => Tool::Rectangle,
+            Tool::Rectangle => Tool::HollowRectangle,
+            Tool::HollowRectangle => Tool::Eraser,
+            Tool::Eraser => Tool::Pencil,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Pencil => "PENCIL",
+            Tool::Fill => "FILL",
+            Tool::Line => "LINE",
+            Tool::Rectangle => "RECTANGLE",
+            Tool::HollowRectangle => "HOLLOW RECTANGLE",
+            Tool::Eraser => "ERASER",
+        }
+    }
+
+    // Shapes are drawn by dragging the mouse from one corner to the other.
+    pub fn shape_cells(
+        self,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Vec<(usize, usize)> {
+        match self {
+            Tool::Line => line_cells(from, to),
+            Tool::Rectangle => rect_cells(from, to, false),
+            Tool::HollowRectangle => rect_cells(from, to, true),
+            _ => vec![to],
+        }
+    }
+}
+
+pub fn line_cells(
+    (x0, y0): (usize, usize),
+    (x1, y1): (usize, usize),
+) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (x0 as isize, y0 as isize);
+    let (x1, y1) = (x1 as isize, y1 as isize);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let step_x = if x < x1 { 1 } else { -1 };
+    let step_y = if y < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let mut cells = vec![];
+    loop {
+        cells.push((x as usize, y as usize));
+        if x == x1 && y == y1 {
+            break cells;
+        }
+
+        let double_error = 2 * error;
+        if double_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if double_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+pub fn rect_cells(
+    from: (usize, usize),
+    to: (usize, usize),
+    hollow: bool,
+) -> Vec<(usize, usize)> {
+    let (left, right) = (from.0.min(to.0), from.0.max(to.0));
+    let (top, bottom) = (from.1.min(to.1), from.1.max(to.1));
+
+    let mut cells = vec![];
+    for y in top..=bottom {
+        for x in left..=right {
+            let on_edge = x == left || x == right || y == top || y == bottom;
+            if !hollow || on_edge {
+                cells.push((x, y));
+            }
+        }
+    }
+    cells
+}
+
+// Square brush centered on the cursor.
+pub fn brush_cells(
+    (x, y): (usize, usize),
+    size: usize,
+) -> Vec<(usize, usize)> {
+    let radius = size / 2;
+    let from = (x.saturating_sub(radius), y.saturating_sub(radius));
+    let to = (
+        (from.0 + size - 1).min(LEVEL_WIDTH - 1),
+        (from.1 + size - 1).min(LEVEL_HEIGHT - 1),
+    );
+    rect_cells(from, to, false)
+}
+
+// Cells connected to the start by edges that satisfy the predicate.
+pub fn flood_fill_cells<F>(
+    start: (usize, usize),
+    matches: F,
+) -> Vec<(usize, usize)>
+where
+    F: Fn((usize, usize)) -> bool,
+{
+    let mut visited = vec![[false; LEVEL_WIDTH]; LEVEL_HEIGHT];
+    let mut queue = VecDeque::new();
+    let mut cells = vec![];
+
+    visited[start.1][start.0] = true;
+    queue.push_back(start);
+
+    while let Some((x, y)) = queue.pop_front() {
+        cells.push((x, y));
+
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+
+        for &(next_x, next_y) in neighbours.iter() {
+            if next_x >= LEVEL_WIDTH
+                || next_y >= LEVEL_HEIGHT
+                || visited[next_y][next_x]
+                || !matches((next_x, next_y))
+            {
+                continue;
+            }
+
+            visited[next_y][next_x] = true;
+            queue.push_back((next_x, next_y));
+        }
+    }
+
+    cells
+}
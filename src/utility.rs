@@ -1,15 +1,20 @@
 use crate::render::*;
 
 use std::error::Error;
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 use sdl2::rect::Rect;
 
+use serde::{Deserialize, Serialize};
+
 use vector2d::Vector2D;
 
 pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
+#[derive(Deserialize, Serialize)]
 pub enum XDirection {
     Left,
     Right,
@@ -27,21 +32,35 @@ impl Frequency {
     }
 
     pub fn phase(&self, tick: u32) -> u32 {
-        tick * self.times / FPS % self.phases
+        let phase = u64::from(tick) * u64::from(self.times) / u64::from(FPS);
+        (phase % u64::from(self.phases)) as u32
     }
 }
 
-pub fn get_base_path() -> Result<PathBuf> {
-    let mut path = std::env::current_exe()?;
-    path = path.parent().unwrap().to_path_buf();
-
-    while !path.ends_with(env!("CARGO_PKG_NAME")) {
-        if !path.pop() {
-            return Err("Couldn't reach base game directory!".into());
-        }
+// Writes the contents next to the file first and then replaces the file,
+// so that a crash in the middle of writing can't leave it half written. The
+// contents are synced before the rename, otherwise a power loss may leave
+// the renamed file empty.
+pub fn write_file_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        let message = format!("Invalid file path {}!", path.display());
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    })?;
+    let temp_path =
+        path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    let written = fs::File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(err) = written {
+        fs::remove_file(&temp_path).ok();
+        return Err(err);
     }
-
-    Ok(path)
+    fs::rename(&temp_path, path).inspect_err(|_| {
+        fs::remove_file(&temp_path).ok();
+    })?;
+    Ok(())
 }
 
 #[macro_export]
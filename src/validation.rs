@@ -0,0 +1,309 @@
+use crate::block::*;
+use crate::enemy::*;
+use crate::entity::*;
+use crate::hitbox::*;
+use crate::level::*;
+use crate::paths::*;
+use crate::player::*;
+use crate::resource::*;
+use crate::utility::*;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// Rough reach of a single jump, measured in blocks.
+const JUMP_HEIGHT: usize = 4;
+const JUMP_DISTANCE: usize = 6;
+
+pub type BlockCoords = (usize, usize);
+
+#[derive(Debug)]
+pub enum LevelIssue {
+    MissingEndFlag,
+    UnreachableEndFlag(BlockCoords),
+    EmbeddedEnemy(EnemyType, BlockCoords),
+    UnbumpableContents(BlockType, BlockCoords),
+    SpawnInsideBlock(BlockCoords),
+}
+
+impl fmt::Display for LevelIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelIssue::MissingEndFlag => write!(f, "level has no end flag"),
+            LevelIssue::UnreachableEndFlag((x, y)) => {
+                write!(f, "({}, {}): end flag can't be reached", x, y)
+            },
+            LevelIssue::EmbeddedEnemy(enemy, (x, y)) => {
+                write!(f, "({}, {}): {:?} is stuck inside a block", x, y, enemy)
+            },
+            LevelIssue::UnbumpableContents(kind, (x, y)) => {
+                write!(
+                    f,
+                    "({}, {}): {:?} has contents but can't be bumped",
+                    x, y, kind
+                )
+            },
+            LevelIssue::SpawnInsideBlock((x, y)) => {
+                write!(f, "({}, {}): player spawns inside a block", x, y)
+            },
+        }
+    }
+}
+
+fn block_coords((x, y): (i32, i32)) -> BlockCoords {
+    let x = x.max(0) as usize / BLOCK_SIZE as usize;
+    let y = y.max(0) as usize / BLOCK_SIZE as usize;
+    (x.min(LEVEL_WIDTH - 1), y.min(LEVEL_HEIGHT - 1))
+}
+
+impl Level {
+    pub fn validate(&self) -> Vec<LevelIssue> {
+        let mut issues = vec![];
+        self.check_blocks(&mut issues);
+        self.check_enemies(&mut issues);
+        self.check_spawn(&mut issues);
+        self.check_end_flag(&mut issues);
+        issues
+    }
+
+    fn is_solid(&self, (x, y): BlockCoords) -> bool {
+        self.get_block((x, y)).is_collidable()
+    }
+
+    fn intersects_blocks(&self, hitbox: Hitbox) -> bool {
+        let (from_x, from_y) = block_coords((hitbox.left(), hitbox.top()));
+        let (to_x, to_y) = block_coords((hitbox.right(), hitbox.bottom()));
+
+        (from_y..=to_y).any(|y| {
+            (from_x..=to_x).any(|x| {
+                self.is_solid((x, y))
+                    && Block::hitbox(x, y).has_intersection(hitbox)
+            })
+        })
+    }
+
+    fn check_blocks(&self, issues: &mut Vec<LevelIssue>) {
+        for y in 0..LEVEL_HEIGHT {
+            for x in 0..LEVEL_WIDTH {
+                let block = self.get_block((x, y));
+                if !block.is_empty() && !block.is_bumpable() {
+                    issues.push(LevelIssue::UnbumpableContents(
+                        block.kind(),
+                        (x, y),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn check_enemies(&self, issues: &mut Vec<LevelIssue>) {
+        for entity in self.entities() {
+            if let EntityType::Enemy(enemy) = entity.kind {
+                if self.intersects_blocks(entity.hitbox()) {
+                    let coords = block_coords(entity.position);
+                    issues.push(LevelIssue::EmbeddedEnemy(enemy, coords));
+                }
+            }
+        }
+    }
+
+    fn check_spawn(&self, issues: &mut Vec<LevelIssue>) {
+        let spawn = Player::spawn(self).rect();
+        if self.intersects_blocks(spawn) {
+            let coords = block_coords((spawn.x(), spawn.y()));
+            issues.push(LevelIssue::SpawnInsideBlock(coords));
+        }
+    }
+
+    fn check_end_flag(&self, issues: &mut Vec<LevelIssue>) {
+        let flag = self
+            .entities()
+            .iter()
+            .find(|entity| matches!(entity.kind, EntityType::EndFlag));
+
+        match flag {
+            None => issues.push(LevelIssue::MissingEndFlag),
+            Some(flag) => {
+                let (flag_x, flag_y) = block_coords(flag.position);
+                let reachable = self.reachable_columns();
+                if !reachable.iter().any(|&x| x + 1 >= flag_x) {
+                    let coords = (flag_x, flag_y);
+                    issues.push(LevelIssue::UnreachableEndFlag(coords));
+                }
+            },
+        }
+    }
+
+    fn is_standable(&self, (x, y): BlockCoords) -> bool {
+        y + 1 < LEVEL_HEIGHT
+            && !self.is_solid((x, y))
+            && self.is_solid((x, y + 1))
+    }
+
+    // The player can swim anywhere in the water, however deep.
+    fn is_swimmable(&self, spot: BlockCoords) -> bool {
+        self.is_water(spot) && !self.is_solid(spot)
+    }
+
+    // The player has to pass every column between two spots somewhere
+    // between the top of the jump and the lower of the two spots.
+    fn is_passable(&self, from: BlockCoords, to: BlockCoords) -> bool {
+        let top = from.1.saturating_sub(JUMP_HEIGHT);
+        let bottom = from.1.max(to.1);
+        let (left, right) = (from.0.min(to.0), from.0.max(to.0));
+
+        (left + 1..right)
+            .all(|x| (top..=bottom).any(|y| !self.is_solid((x, y))))
+    }
+
+    fn landing_spot(&self, (x, mut y): BlockCoords) -> Option<BlockCoords> {
+        while y < LEVEL_HEIGHT {
+            if self.is_standable((x, y)) || self.is_swimmable((x, y)) {
+                return Some((x, y));
+            }
+            y += 1;
+        }
+        None
+    }
+
+    // Spots within a single jump from the given one.
+    fn jump_targets(&self, (x, y): BlockCoords) -> Vec<BlockCoords> {
+        let from_x = x.saturating_sub(JUMP_DISTANCE);
+        let to_x = (x + JUMP_DISTANCE).min(LEVEL_WIDTH - 1);
+        let from_y = y.saturating_sub(JUMP_HEIGHT);
+
+        let mut targets = vec![];
+        for next_y in from_y..LEVEL_HEIGHT {
+            for next_x in from_x..=to_x {
+                let next = (next_x, next_y);
+                let reachable = self.is_standable(next)
+                    || self.is_swimmable(next);
+                if reachable && self.is_passable((x, y), next) {
+                    targets.push(next);
+                }
+            }
+        }
+        targets
+    }
+
+    // Water cells next to the given spot, in any direction.
+    fn swim_targets(&self, (x, y): BlockCoords) -> Vec<BlockCoords> {
+        let mut targets = vec![];
+        if x > 0 {
+            targets.push((x - 1, y));
+        }
+        if x + 1 < LEVEL_WIDTH {
+            targets.push((x + 1, y));
+        }
+        if y > 0 {
+            targets.push((x, y - 1));
+        }
+        if y + 1 < LEVEL_HEIGHT {
+            targets.push((x, y + 1));
+        }
+        targets.retain(|&next| self.is_swimmable(next));
+        targets
+    }
+
+    // Where the player comes out of the other warp with the same link.
+    fn warp_exit(&self, from: usize) -> Option<BlockCoords> {
+        let link = self.entities()[from].properties.warp?;
+        let target = self.entities().iter().enumerate().find(|(i, e)| {
+            *i != from
+                && matches!(e.kind, EntityType::Warp)
+                && e.properties.warp == Some(link)
+        });
+
+        let (_, target) = target?;
+        let hitbox = target.hitbox();
+        self.landing_spot(block_coords((hitbox.left(), hitbox.bottom() - 1)))
+    }
+
+    // Warps are entered from within a jump, or by climbing a vine from a
+    // block in the same column up into them.
+    fn warp_targets(&self, (x, y): BlockCoords) -> Vec<BlockCoords> {
+        let from_y = y.saturating_sub(JUMP_HEIGHT);
+        let vine = (from_y..y).find(|&block_y| {
+            let contents = self.get_block((x, block_y)).get_contents();
+            matches!(contents, Some(Collectible::Vine))
+        });
+
+        let warps = self.entities().iter().enumerate().filter(|(_, e)| {
+            if !matches!(e.kind, EntityType::Warp) {
+                return false;
+            }
+            let hitbox = e.hitbox();
+            let (left, top) = block_coords((hitbox.left(), hitbox.top()));
+            let (right, bottom) =
+                block_coords((hitbox.right() - 1, hitbox.bottom() - 1));
+            let in_jump = top <= y && bottom >= from_y;
+            let above_vine = vine.is_some_and(|block_y| top < block_y);
+            left <= x && x <= right && (in_jump || above_vine)
+        });
+
+        warps.filter_map(|(i, _)| self.warp_exit(i)).collect()
+    }
+
+    // Walks over every spot the player can stand or swim in, starting from
+    // the spawn.
+    fn reachable_columns(&self) -> Vec<usize> {
+        let spawn = Player::spawn(self).rect().center();
+        let start = match self.landing_spot(block_coords(spawn.into())) {
+            Some(start) => start,
+            None => return vec![],
+        };
+
+        let mut visited = vec![[false; LEVEL_WIDTH]; LEVEL_HEIGHT];
+        let mut queue = VecDeque::new();
+        visited[start.1][start.0] = true;
+        queue.push_back(start);
+
+        let mut columns = vec![];
+        while let Some(spot) = queue.pop_front() {
+            columns.push(spot.0);
+
+            let mut next = self.jump_targets(spot);
+            next.extend(self.swim_targets(spot));
+            next.extend(self.warp_targets(spot));
+            for (next_x, next_y) in next {
+                if !visited[next_y][next_x] {
+                    visited[next_y][next_x] = true;
+                    queue.push_back((next_x, next_y));
+                }
+            }
+        }
+
+        columns
+    }
+}
+
+// Checks the given level files or, if none were given, the whole level
+// pack. Returns whether every level is free of issues.
+pub fn validate_levels(
+    game_paths: &GamePaths,
+    pack: &str,
+    paths: &[String],
+) -> Result<bool> {
+    let levels: Vec<PathBuf> = if paths.is_empty() {
+        read_level_list(game_paths, pack)?
+            .into_iter()
+            .map(|name| level_path(game_paths, &name))
+            .collect()
+    } else {
+        paths.iter().map(PathBuf::from).collect()
+    };
+
+    let mut valid = true;
+    for path in levels.iter() {
+        let level = read_level(path)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+        for issue in level.validate() {
+            println!("{}: {}", path.display(), issue);
+            valid = false;
+        }
+    }
+
+    Ok(valid)
+}
@@ -0,0 +1,87 @@
+use crate::render::*;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Directories are scanned once per second, which is cheap enough for the
+// few hundred files of the game and needs no platform specific API.
+const POLL_INTERVAL: u32 = FPS;
+
+// Notices files that were changed, added or removed in a set of directories
+// by comparing their modification times.
+pub struct FileWatcher {
+    dirs:  Vec<PathBuf>,
+    files: HashMap<PathBuf, SystemTime>,
+    timer: u32,
+}
+
+impl FileWatcher {
+    pub fn new(dirs: Vec<PathBuf>) -> FileWatcher {
+        let files = scan(&dirs);
+        FileWatcher {
+            dirs,
+            files,
+            timer: 0,
+        }
+    }
+
+    // Returns the files that changed since the last scan, the directories
+    // are scanned only every `POLL_INTERVAL` calls.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        self.timer += 1;
+        if self.timer < POLL_INTERVAL {
+            return vec![];
+        }
+        self.timer = 0;
+        self.rescan()
+    }
+
+    pub fn rescan(&mut self) -> Vec<PathBuf> {
+        let files = scan(&self.dirs);
+        let mut changed: Vec<PathBuf> = files
+            .iter()
+            .filter(|(path, time)| self.files.get(*path) != Some(time))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let removed = self
+            .files
+            .keys()
+            .filter(|path| !files.contains_key(*path))
+            .cloned();
+        changed.extend(removed);
+
+        self.files = files;
+        changed
+    }
+}
+
+fn scan(dirs: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    let mut files = HashMap::new();
+    for dir in dirs {
+        scan_dir(dir, &mut files);
+    }
+    files
+}
+
+fn scan_dir(dir: &Path, files: &mut HashMap<PathBuf, SystemTime>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let meta = match entry.metadata() {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+
+        if meta.is_dir() {
+            scan_dir(&path, files);
+        } else if let Ok(modified) = meta.modified() {
+            files.insert(path, modified);
+        }
+    }
+}